@@ -1,7 +1,14 @@
+use std::collections::BTreeMap;
+
 use clap::ValueEnum;
 use crossterm::{style::Color, terminal::WindowSize};
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
+use crate::editor::LineEditor;
+use crate::keymap::Keymap;
+use crate::settings::Settings;
+
 pub const COLORS: [Color; 18] = [
     Color::AnsiValue(98),
     Color::AnsiValue(176),
@@ -26,21 +33,74 @@ pub const COLORS: [Color; 18] = [
 #[derive(Debug, Deserialize, Serialize)]
 pub struct App {
     pub filename: String,
+    #[serde(skip)]
+    pub argument: char,
     pub change: u8,
-    pub command: String,
+    #[serde(skip)]
+    pub command: LineEditor,
+    #[serde(skip)]
+    pub count: usize,
     pub cursor_column: usize,
     pub cursor_row: usize,
+    #[serde(skip)]
+    pub desired_column: Option<(usize, usize, usize)>,
+    #[serde(skip)]
+    pub find: Option<(char, char)>,
+    #[serde(skip)]
+    pub help_query: String,
+    #[serde(skip)]
+    pub help_row: usize,
+    #[serde(skip)]
+    pub is_dirty: bool,
+    #[serde(skip)]
+    pub is_linewise: bool,
+    #[serde(skip)]
+    pub jump_index: usize,
+    #[serde(skip)]
+    pub jumps: Vec<usize>,
+    #[serde(skip)]
+    pub keymap: Keymap,
     pub labels: Vec<Label>,
     pub lines: Vec<Line>,
+    #[serde(default)]
+    pub marks: BTreeMap<char, usize>,
+    #[serde(skip)]
+    pub message: String,
     pub modal_active: usize,
     pub modal_column: usize,
+    #[serde(skip)]
+    pub modal_filter: LineEditor,
+    #[serde(skip)]
+    pub modal_offset: usize,
     pub modal_row: usize,
     pub modal_start_column: usize,
     pub modal_start_row: usize,
     pub mode: Mode,
+    #[serde(skip)]
+    pub name: LineEditor,
     pub nlines: usize,
+    #[serde(default)]
+    pub offset_column: usize,
     pub offset_row: usize,
+    #[serde(skip)]
+    pub operator: Option<char>,
+    #[serde(skip)]
+    pub operator_count: usize,
+    #[serde(skip)]
+    pub pending: String,
+    #[serde(skip)]
+    pub picker: LineEditor,
+    #[serde(skip)]
+    pub picker_row: usize,
+    #[serde(skip)]
+    pub review_tags: Vec<(usize, Tag)>,
     pub rng: usize,
+    #[serde(default)]
+    pub sentences: Vec<(usize, usize)>,
+    #[serde(skip)]
+    pub settings: Settings,
+    #[serde(default)]
+    pub tag_count: usize,
     pub visual: Vec<Visual>,
     pub window_height: usize,
     pub window_width: usize,
@@ -51,27 +111,56 @@ impl App {
         let window_height = window.rows as usize;
         let window_width = window.columns as usize;
 
-        App {
+        let mut app = App {
             filename: filename.to_owned(),
+            argument: '\x00',
             change: 0,
-            command: String::new(),
+            command: LineEditor::default(),
+            count: 0,
             cursor_column: 0,
             cursor_row: 0,
+            desired_column: None,
+            find: None,
+            help_query: String::new(),
+            help_row: 0,
+            is_dirty: false,
+            is_linewise: false,
+            jump_index: 0,
+            jumps: Vec::new(),
+            keymap: Keymap::default(),
             nlines: lines.len(),
             labels,
             lines,
+            marks: BTreeMap::new(),
+            message: String::new(),
             modal_active: 0,
             modal_column: 0,
+            modal_filter: LineEditor::default(),
+            modal_offset: 0,
             modal_row: 0,
-            modal_start_column: window_width / 2 - 20,
-            modal_start_row: window_height / 2 - 12,
+            modal_start_column: 0,
+            modal_start_row: 0,
             mode: Mode::Normal,
+            name: LineEditor::default(),
+            offset_column: 0,
             offset_row: 0,
+            operator: None,
+            operator_count: 0,
+            pending: String::new(),
+            picker: LineEditor::default(),
+            picker_row: 0,
+            review_tags: Vec::new(),
             rng: 0,
+            sentences: Vec::new(),
+            settings: Settings::default(),
+            tag_count: 0,
             visual: Vec::new(),
             window_height,
             window_width,
-        }
+        };
+
+        app.set_modal_position();
+        app
     }
 
     pub fn get_visual_bounds(&self, row: usize) -> (usize, usize) {
@@ -94,57 +183,265 @@ impl App {
         self.lines[self.cursor_row + self.offset_row].width
     }
 
+    pub fn get_span(&self, row: usize, position: usize) -> Vec<(usize, usize)> {
+        let mut span = vec![(row, position)];
+
+        while let Some(&(row, position)) = span.first() {
+            match self.find_line_prev(&self.lines[row].tags[position], row) {
+                Some(prev) => span.insert(0, (row - 1, prev)),
+                None => break,
+            }
+        }
+
+        while let Some(&(row, position)) = span.last() {
+            match self.find_line_next(&self.lines[row].tags[position], row) {
+                Some(next) => span.push((row + 1, next)),
+                None => break,
+            }
+        }
+
+        span
+    }
+
+    pub fn set_window(&mut self, window: WindowSize) {
+        self.window_height = window.rows as usize;
+        self.window_width = window.columns as usize;
+        self.set_modal_position();
+        self.manage_modal_offset();
+    }
+
+    pub fn set_modal_position(&mut self) {
+        let (width, height) = self.get_modal_size();
+
+        self.modal_start_column = self.window_width.saturating_sub(width + 2) / 2;
+        self.modal_start_row = (self.window_height.saturating_sub(1) / 2).saturating_sub((height + 2) / 2);
+    }
+
+    pub fn get_text_width(&self) -> usize {
+        let width = self.window_width.saturating_sub(self.get_left_margin() + 2);
+
+        if self.settings.is_overflow {
+            usize::MAX
+        } else if self.settings.text_width > 0 {
+            std::cmp::max(std::cmp::min(self.settings.text_width, width), 1)
+        } else {
+            std::cmp::max(width, 1)
+        }
+    }
+
+    pub fn get_left_margin(&self) -> usize {
+        let width = self.window_width.saturating_sub(2);
+
+        if self.settings.is_centered && self.settings.text_width > 0 && !self.settings.is_overflow {
+            width.saturating_sub(self.settings.text_width) / 2
+        } else {
+            std::cmp::min(self.settings.margin, width.saturating_sub(1))
+        }
+    }
+
+    pub fn get_text(&self) -> String {
+        let mut text = String::new();
+
+        for (i, line) in self.lines.iter().enumerate() {
+            if i > 0 && !line.is_virtual {
+                text.push('\n');
+            }
+
+            text.push_str(&line.text);
+        }
+
+        text
+    }
+
+    pub fn get_absolute_position(&self, row: usize, column: usize) -> usize {
+        self.lines[row].absolute_offset + column
+    }
+
+    pub fn find_absolute_position(&self, position: usize) -> (usize, usize) {
+        let row = self
+            .lines
+            .partition_point(|x| x.absolute_offset <= position)
+            .saturating_sub(1);
+        let line = &self.lines[row];
+
+        (
+            row,
+            std::cmp::min(position - line.absolute_offset, line.width.saturating_sub(1)),
+        )
+    }
+
+    pub fn get_spans(&self) -> Vec<Span> {
+        self.get_span_heads()
+            .into_iter()
+            .map(|(row, position)| {
+                let head = &self.lines[row].tags[position];
+                let (tail_row, tail_position) = *self.get_span(row, position).last().expect("Error retrieving span");
+
+                Span {
+                    start: self.get_absolute_position(row, head.start),
+                    end: self.get_absolute_position(tail_row, self.lines[tail_row].tags[tail_position].end),
+                    label: head.label,
+                    is_reviewed: head.is_reviewed,
+                }
+            })
+            .collect()
+    }
+
+    pub fn get_span_heads(&self) -> Vec<(usize, usize)> {
+        self.lines
+            .iter()
+            .enumerate()
+            .flat_map(|(row, line)| {
+                line.tags
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, x)| !x.has_line_prev)
+                    .map(move |(position, x)| (row, x.start, position))
+            })
+            .sorted()
+            .map(|(row, _, position)| (row, position))
+            .collect()
+    }
+
     pub fn tag(&mut self) {
-        let (s, e) = self.get_visual_bounds(self.cursor_row + self.offset_row);
-
-        if e - s > 1 {
-            let tag = Tag {
-                start: s,
-                end: e,
-                label: self.modal_active,
-                has_line_next: false,
-                has_line_prev: false,
-            };
-            self.lines[self.cursor_row + self.offset_row].tags.push(tag);
+        self.tag_with(self.modal_active);
+    }
+
+    pub fn tag_with(&mut self, label: usize) {
+        let regions = self
+            .visual
+            .iter()
+            .map(|x| {
+                let (s, e) = self.get_visual_bounds(x.row);
+                (x.row, (s, std::cmp::min(e, self.lines[x.row].width)))
+            })
+            .filter(|(_, (s, e))| e > s)
+            .sorted()
+            .collect::<Vec<(usize, (usize, usize))>>();
+
+        if regions.iter().map(|(_, (s, e))| e - s).sum::<usize>() > 1 {
+            for (row, tag) in &self.review_tags {
+                if let Some(position) = self.lines[*row].tags.iter().position(|x| x == tag) {
+                    self.lines[*row].tags.remove(position);
+                }
+            }
+
+            for (i, &(row, (s, e))) in regions.iter().enumerate() {
+                let tag = Tag {
+                    start: s,
+                    end: e,
+                    label,
+                    has_line_next: regions.get(i + 1).is_some_and(|x| x.0 == row + 1),
+                    has_line_prev: i > 0 && regions[i - 1].0 + 1 == row,
+                    is_reviewed: true,
+                };
+                self.lines[row].tags.push(tag);
+            }
+
+            self.tag_count += 1;
+            self.labels[label].last_used = self.tag_count;
+            self.is_dirty = true;
             self.change = 0b0011;
         }
 
         self.visual.clear();
     }
 
-    pub fn untag(&mut self) {
-        let position_maybe = self
-            .get_current_line()
-            .tags
-            .iter()
-            .position(|x| x.start <= self.cursor_column && self.cursor_column < x.end);
+    pub fn tag_range(&mut self, start: usize, end: usize) {
+        let text = self.get_text();
+        let start = end - text[start..end].trim_start().len();
+        let end = start + text[start..end].trim_end().len();
+
+        if end > start {
+            let anchor = self.find_absolute_position(start);
+            let position = self.find_absolute_position(end - 1);
+
+            self.visual = self.get_visual_regions(anchor, position);
+            self.tag();
+        }
+    }
+
+    /// Spans overlapping `start..end` when `is_partial`, otherwise only those lying entirely inside it.
+    pub fn get_spans_in_range(&self, start: usize, end: usize, is_partial: bool) -> Vec<Vec<(usize, usize)>> {
+        self.get_span_heads()
+            .into_iter()
+            .map(|(row, position)| self.get_span(row, position))
+            .filter(|span| {
+                let (head_row, head) = span[0];
+                let (tail_row, tail) = span[span.len() - 1];
+
+                let head = self.get_absolute_position(head_row, self.lines[head_row].tags[head].start);
+                let tail = self.get_absolute_position(tail_row, self.lines[tail_row].tags[tail].end);
+
+                match is_partial {
+                    true => head < end && start < tail,
+                    false => start <= head && tail <= end,
+                }
+            })
+            .collect()
+    }
+
+    pub fn untag_range(&mut self, start: usize, end: usize, label: Option<usize>, is_partial: bool) {
+        let tags = self
+            .get_spans_in_range(start, end, is_partial)
+            .into_iter()
+            .filter(|span| label.is_none_or(|x| self.lines[span[0].0].tags[span[0].1].label == x))
+            .flatten()
+            .sorted()
+            .rev()
+            .collect::<Vec<(usize, usize)>>();
+
+        for &(row, position) in &tags {
+            self.lines[row].tags.remove(position);
+        }
 
-        if let Some(position) = position_maybe {
-            let row = self.cursor_row + self.offset_row;
-            let tag = self.lines[row].tags.remove(position);
+        if !tags.is_empty() {
+            self.is_dirty = true;
+            self.change = 0b0011;
+        }
+    }
 
-            self.untag_next(&tag, row);
-            self.untag_prev(&tag, row);
+    pub fn relabel_range(&mut self, start: usize, end: usize, label: Option<usize>, other: usize) {
+        let tags = self
+            .get_spans_in_range(start, end, false)
+            .into_iter()
+            .filter(|span| label.is_none_or(|x| self.lines[span[0].0].tags[span[0].1].label == x))
+            .flatten()
+            .collect::<Vec<(usize, usize)>>();
+
+        for &(row, position) in &tags {
+            let tag = &mut self.lines[row].tags[position];
+            tag.label = other;
+            tag.is_reviewed = true;
+        }
 
+        if !tags.is_empty() {
+            self.is_dirty = true;
             self.change = 0b0011;
         }
     }
 
-    fn untag_next(&mut self, tag: &Tag, row: usize) {
-        if tag.has_line_next {
-            let tag_next = self.lines[row + 1].tags.remove(0);
-            self.untag_next(&tag_next, row + 1);
+    fn find_line_next(&self, tag: &Tag, row: usize) -> Option<usize> {
+        if !tag.has_line_next {
+            return None;
         }
+
+        self.lines
+            .get(row + 1)?
+            .tags
+            .iter()
+            .position(|x| x.has_line_prev && x.start == 0 && x.label == tag.label)
     }
 
-    fn untag_prev(&mut self, tag: &Tag, row: usize) {
-        if tag.has_line_prev {
-            let tag_prev = self.lines[row - 1]
-                .tags
-                .pop()
-                .expect("Error dereferencing previous tag");
-            self.untag_prev(&tag_prev, row - 1);
+    fn find_line_prev(&self, tag: &Tag, row: usize) -> Option<usize> {
+        if !tag.has_line_prev {
+            return None;
         }
+
+        let line = self.lines.get(row.checked_sub(1)?)?;
+        line.tags
+            .iter()
+            .rposition(|x| x.has_line_next && x.end == line.width && x.label == tag.label)
     }
 }
 
@@ -161,6 +458,10 @@ pub struct Label {
     pub color: Color,
     pub is_active: bool,
     pub is_visible: bool,
+    #[serde(default)]
+    pub hotkey: Option<char>,
+    #[serde(default)]
+    pub last_used: usize,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -178,12 +479,24 @@ pub struct Line {
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub enum Mode {
     Command,
+    Filter,
+    Help,
     Modal,
     Name,
     Normal,
+    Picker,
+    Review,
     Visual,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub label: usize,
+    pub is_reviewed: bool,
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Tag {
     pub start: usize,
@@ -191,6 +504,8 @@ pub struct Tag {
     pub label: usize,
     pub has_line_prev: bool,
     pub has_line_next: bool,
+    #[serde(default)]
+    pub is_reviewed: bool,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -1,12 +1,15 @@
-use std::io::Stdout;
-
 use anyhow::Result;
 
 use crate::{
     app::{App, Mode},
-    io, render,
+    common, io, render,
+    settings::{self, parse_number},
 };
 
+pub const COMMANDS: &[&str] = &[
+    "debug", "edit", "goto", "help", "quit", "relabel", "retag", "saveas", "set", "untag", "wq", "write",
+];
+
 impl App {
     pub fn is_command_mode(&self) -> bool {
         self.mode == Mode::Command
@@ -17,13 +20,62 @@ impl App {
     }
 
     pub fn command_char(&mut self, c: char) {
-        self.command.push(c);
+        self.command.insert(c);
         self.change |= 0b_0010_0000;
     }
 
     pub fn command_backspace(&mut self) {
-        if !self.command.is_empty() {
-            self.command.pop();
+        if self.command.backspace() {
+            self.change |= 0b_0010_0000;
+        }
+    }
+
+    pub fn command_left(&mut self) {
+        if self.command.left() {
+            self.change |= 0b_0010_0000;
+        }
+    }
+
+    pub fn command_right(&mut self) {
+        if self.command.right() {
+            self.change |= 0b_0010_0000;
+        }
+    }
+
+    pub fn command_up(&mut self) {
+        if self.command.history_prev() {
+            self.change |= 0b_0010_0000;
+        }
+    }
+
+    pub fn command_down(&mut self) {
+        if self.command.history_next() {
+            self.change |= 0b_0010_0000;
+        }
+    }
+
+    pub fn command_delete_line(&mut self) {
+        if self.command.delete_line() {
+            self.change |= 0b_0010_0000;
+        }
+    }
+
+    pub fn command_delete_word(&mut self) {
+        if self.command.delete_word() {
+            self.change |= 0b_0010_0000;
+        }
+    }
+
+    pub fn command_tab(&mut self) {
+        let text = &self.command.text[..self.command.cursor];
+
+        let candidates = match text.split_once(' ') {
+            None => COMMANDS.iter().map(|x| x.to_string()).collect(),
+            Some(("set", _)) => settings::OPTIONS.iter().map(|x| x.to_string()).collect(),
+            Some(_) => self.labels.iter().map(|x| x.name.clone()).collect::<Vec<String>>(),
+        };
+
+        if self.command.complete(&candidates) {
             self.change |= 0b_0010_0000;
         }
     }
@@ -31,35 +83,353 @@ impl App {
     pub fn command_esc(&mut self) {
         self.command.clear();
         self.set_normal_mode();
-        self.change |= 0b_0000_0001;
+        self.change |= 0b_0000_0011;
     }
 
-    pub fn command_return(&mut self, stdout: &mut Stdout) -> Result<()> {
+    pub fn command_return(&mut self) {
         self.change |= 0b0001;
-        match self.command.as_str() {
-            "q" | "quit" => execute_exit(stdout),
-            "w" | "write" => execute_write(self),
+        self.command.push_history();
+
+        let text = self.command.text.clone();
+        self.command.clear();
+        self.set_normal_mode();
+
+        let command = parse_command(&text);
+        let result = match command.name {
+            "" if command.range.is_empty() => Ok(()),
+            "" => match command.range.strip_suffix('%') {
+                Some(percent) if !percent.is_empty() => execute_percent(self, percent),
+                _ => execute_line(self, command.range),
+            },
+            "untag" => execute_untag(self, command.range, command.argument),
+            "relabel" => execute_relabel(self, command.range, command.argument),
+            "retag" => execute_retag(self, command.range, command.argument),
+            _ if !command.range.is_empty() => Err(anyhow::Error::msg("No range allowed")),
+            "q" | "quit" => execute_quit(self, command.is_forced),
+            "w" | "write" => execute_write(self, command.argument),
+            "wq" | "x" => execute_write(self, command.argument).and_then(|_| execute_exit()),
+            "sav" | "saveas" => execute_saveas(self, command.argument),
+            "e" | "edit" => execute_edit(self, command.argument, command.is_forced),
             "d" | "debug" => execute_debug(self),
-            _ => Ok(()),
+            "h" | "help" => {
+                self.open_help(command.argument);
+                Ok(())
+            }
+            "set" => execute_set(self, command.argument),
+            "goto" => execute_goto(self, command.argument),
+            name => Err(anyhow::Error::msg(format!("Unknown command: {}", name))),
+        };
+
+        if let Err(e) = result {
+            self.message = e.to_string();
+        }
+    }
+}
+
+struct Command<'a> {
+    range: &'a str,
+    name: &'a str,
+    is_forced: bool,
+    argument: &'a str,
+}
+
+fn parse_command(s: &str) -> Command<'_> {
+    let s = s.trim();
+
+    let mut end = 0;
+    let mut chars = s.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\'' => end = chars.next().map_or(s.len(), |(j, x)| j + x.len_utf8()),
+            c if c.is_ascii_digit() || ",.$%".contains(c) => end = i + 1,
+            _ => break,
         }
     }
+
+    let (range, rest) = s.split_at(end);
+    let (name, rest) = rest.split_at(rest.find(|x: char| !x.is_ascii_alphabetic()).unwrap_or(rest.len()));
+
+    let (is_forced, rest) = match rest.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, rest),
+    };
+
+    Command {
+        range,
+        name,
+        is_forced,
+        argument: rest.trim(),
+    }
+}
+
+fn parse_range(app: &App, range: &str) -> Result<(usize, usize)> {
+    if range == "%" {
+        return Ok((0, app.lines.last().map_or(0, |x| x.absolute_row)));
+    }
+
+    let (first, last) = range.split_once(',').unwrap_or((range, range));
+    let (first, last) = (parse_address(app, first)?, parse_address(app, last)?);
+
+    if first > last {
+        return Err(anyhow::Error::msg("Backwards range given"));
+    }
+
+    Ok((first, last))
+}
+
+fn parse_address(app: &App, address: &str) -> Result<usize> {
+    let last = app.lines.last().map_or(0, |x| x.absolute_row);
+
+    let position = match address {
+        "" | "." => return Ok(app.get_current_line().absolute_row),
+        "$" => return Ok(last),
+        "'<" => app.get_visual_range().map(|(start, _)| start),
+        "'>" => app.get_visual_range().map(|(_, end)| end.saturating_sub(1)),
+        _ if address.starts_with('\'') => address[1..].chars().next().and_then(|x| app.marks.get(&x).copied()),
+        _ => return Ok(std::cmp::min(parse_number(address)?.saturating_sub(1), last)),
+    };
+
+    match position {
+        Some(position) => Ok(app.lines[app.find_absolute_position(position).0].absolute_row),
+        None => Err(anyhow::Error::msg(format!("Mark not set: {}", &address[1..]))),
+    }
+}
+
+fn get_range_bounds(app: &App, range: &str) -> Result<(usize, usize)> {
+    let (first, last) = parse_range(app, range)?;
+
+    let start = app.lines.partition_point(|x| x.absolute_row < first);
+    let end = app.lines.partition_point(|x| x.absolute_row <= last) - 1;
+
+    Ok((
+        app.get_absolute_position(start, 0),
+        app.get_absolute_position(end, app.lines[end].width),
+    ))
+}
+
+fn find_label(app: &App, name: &str) -> Result<usize> {
+    app.labels
+        .iter()
+        .position(|x| x.name == name)
+        .ok_or_else(|| anyhow::Error::msg(format!("Unknown label: {}", name)))
+}
+
+fn execute_untag(app: &mut App, range: &str, argument: &str) -> Result<()> {
+    let (start, end) = get_range_bounds(app, range)?;
+    let label = match argument {
+        "" => None,
+        name => Some(find_label(app, name)?),
+    };
+
+    app.untag_range(start, end, label, false);
+    Ok(())
+}
+
+fn execute_relabel(app: &mut App, range: &str, argument: &str) -> Result<()> {
+    let (start, end) = get_range_bounds(app, range)?;
+    let other = find_label(app, argument)?;
+
+    app.relabel_range(start, end, None, other);
+    Ok(())
+}
+
+fn execute_retag(app: &mut App, range: &str, argument: &str) -> Result<()> {
+    let (start, end) = get_range_bounds(app, range)?;
+    let (label, other) = match argument.split_whitespace().collect::<Vec<&str>>()[..] {
+        [label, other] => (find_label(app, label)?, find_label(app, other)?),
+        _ => return Err(anyhow::Error::msg("Usage: retag LABEL LABEL")),
+    };
+
+    app.relabel_range(start, end, Some(label), other);
+    Ok(())
+}
+
+fn execute_quit(app: &mut App, is_forced: bool) -> Result<()> {
+    if app.is_dirty && !is_forced {
+        return Err(anyhow::Error::msg("No write since last change (add ! to override)"));
+    }
+
+    execute_exit()
 }
 
 #[allow(unreachable_code)]
-fn execute_exit(stdout: &mut Stdout) -> Result<()> {
-    render::render_terminal(stdout);
+fn execute_exit() -> Result<()> {
+    render::render_terminal(&mut std::io::stdout());
     std::process::exit(0);
     Ok(())
 }
 
-fn execute_write(app: &mut App) -> Result<()> {
-    app.command.clear();
-    app.set_normal_mode();
-    io::save_tan(app)
+fn execute_write(app: &mut App, filename: &str) -> Result<()> {
+    if !filename.is_empty() {
+        return io::save_tan(app, &io::get_tan_name(filename));
+    }
+
+    app.filename = io::get_tan_name(&app.filename);
+    io::save_tan(app, &app.filename.clone())?;
+    app.is_dirty = false;
+    Ok(())
+}
+
+fn execute_saveas(app: &mut App, filename: &str) -> Result<()> {
+    if filename.is_empty() {
+        return Err(anyhow::Error::msg("Argument required"));
+    }
+
+    app.filename = io::get_tan_name(filename);
+    execute_write(app, "")
+}
+
+fn execute_edit(app: &mut App, filename: &str, is_forced: bool) -> Result<()> {
+    if app.is_dirty && !is_forced {
+        return Err(anyhow::Error::msg("No write since last change (add ! to override)"));
+    }
+
+    let filename = if filename.is_empty() {
+        app.filename.clone()
+    } else {
+        filename.to_owned()
+    };
+    let mut other = io::open_file(&filename, None)?;
+
+    other.command = std::mem::take(&mut app.command);
+    other.name = std::mem::take(&mut app.name);
+    other.keymap = std::mem::take(&mut app.keymap);
+    other.settings = std::mem::take(&mut app.settings);
+    other.set_modal_position();
+
+    *app = other;
+    io::rewrap(app);
+    app.change |= 0b1111;
+    Ok(())
 }
 
 fn execute_debug(app: &mut App) -> Result<()> {
-    app.command.clear();
-    app.set_normal_mode();
     io::dump_debug(app)
 }
+
+fn execute_line(app: &mut App, range: &str) -> Result<()> {
+    let (_, last) = parse_range(app, range)?;
+    move_to_line(app, last);
+    Ok(())
+}
+
+fn execute_percent(app: &mut App, percent: &str) -> Result<()> {
+    let percent = std::cmp::min(parse_number(percent)?, 100);
+    let nrows = app.lines.last().map_or(0, |x| x.absolute_row + 1);
+
+    move_to_line(app, (percent * nrows).div_ceil(100).saturating_sub(1));
+    Ok(())
+}
+
+/// Jumps to a character offset in the text, as counted by Spacy and in model error reports.
+fn execute_goto(app: &mut App, offset: &str) -> Result<()> {
+    let offset = parse_number(offset)?;
+    let text = app.get_text();
+    let position = text.char_indices().nth(offset).map_or(text.len(), |(x, _)| x);
+
+    common::push_jump(app);
+    common::move_to_absolute(app, position);
+    Ok(())
+}
+
+fn move_to_line(app: &mut App, absolute_row: usize) {
+    let absolute_row = std::cmp::min(absolute_row, app.lines.last().map_or(0, |x| x.absolute_row));
+    let row = app.lines.partition_point(|x| x.absolute_row < absolute_row);
+
+    common::push_jump(app);
+    common::move_to_position(app, row, 0);
+}
+
+fn execute_set(app: &mut App, argument: &str) -> Result<()> {
+    if argument.is_empty() || argument == "all" {
+        app.open_options();
+        return Ok(());
+    }
+
+    let mut is_rewrapped = false;
+
+    for option in argument.split_whitespace() {
+        is_rewrapped |= settings::set_option(app, option)?;
+    }
+
+    if is_rewrapped {
+        io::rewrap(app);
+    }
+
+    app.change |= 0b1111;
+    Ok(())
+}
+
+#[test]
+fn test_parse_command() {
+    let command = parse_command(" w other.tan ");
+    assert!(command.name == "w" && !command.is_forced && command.argument == "other.tan");
+
+    let command = parse_command("q!");
+    assert!(command.name == "q" && command.is_forced && command.argument.is_empty());
+
+    let command = parse_command("50%");
+    assert!(command.range == "50%" && command.name.is_empty());
+
+    let command = parse_command("'<,'>retag PER PERSON");
+    assert!(command.range == "'<,'>" && command.name == "retag" && command.argument == "PER PERSON");
+}
+
+#[test]
+fn test_execute_goto() {
+    let mut app = crate::io::load_test_app("naïve\ncafé au lait", &[(0, 6, "PER")]);
+    let mut goto = |offset: &str| {
+        execute_goto(&mut app, offset).unwrap();
+        (app.cursor_row + app.offset_row, app.cursor_column)
+    };
+
+    assert!(goto("6") == (1, 0));
+    assert!(goto("10") == (1, 5));
+    assert!(goto("3") == (0, 4));
+    assert!(goto("999") == (1, 12));
+}
+
+#[test]
+fn test_parse_range() {
+    let mut app = crate::io::load_test_app("alpha\nbeta\ngamma\ndelta", &[(0, 5, "PER")]);
+    app.cursor_row = 1;
+    app.marks.insert('a', 13);
+
+    assert!(parse_address(&app, ".").unwrap() == 1);
+    assert!(parse_address(&app, "").unwrap() == 1);
+    assert!(parse_address(&app, "$").unwrap() == 3);
+    assert!(parse_address(&app, "3").unwrap() == 2);
+    assert!(parse_address(&app, "99").unwrap() == 3);
+    assert!(parse_address(&app, "'a").unwrap() == 2);
+    assert!(parse_address(&app, "'b").is_err());
+    assert!(parse_address(&app, "'<").is_err());
+    assert!(parse_address(&app, "x").is_err());
+
+    assert!(parse_range(&app, "%").unwrap() == (0, 3));
+    assert!(parse_range(&app, "2,'a").unwrap() == (1, 2));
+    assert!(parse_range(&app, ".,$").unwrap() == (1, 3));
+    assert!(parse_range(&app, "3,1").is_err());
+
+    "vj".chars().for_each(|x| app.dispatch(x));
+    assert!(parse_range(&app, "'<,'>").unwrap() == (1, 2));
+}
+
+#[test]
+fn test_untag_range() {
+    let text = "alpha beta\ngamma delta\nepsilon";
+    let ents = [(0, 5, "PER"), (6, 16, "ORG"), (17, 22, "PER"), (23, 30, "ORG")];
+    let tags = |app: &App| app.lines.iter().map(|x| x.tags.len()).collect::<Vec<usize>>();
+
+    let mut app = crate::io::load_test_app(text, &ents);
+    execute_untag(&mut app, "1", "").unwrap();
+    assert!(tags(&app) == [1, 2, 1]);
+
+    let mut app = crate::io::load_test_app(text, &ents);
+    execute_untag(&mut app, "1,2", "PER").unwrap();
+    assert!(tags(&app) == [1, 1, 1]);
+
+    let mut app = crate::io::load_test_app(text, &ents);
+    execute_retag(&mut app, "%", "ORG PER").unwrap();
+    assert!(app.lines.iter().flat_map(|x| &x.tags).all(|x| x.label == 0));
+}
@@ -1,15 +1,80 @@
-use crate::app::App;
+use crate::app::{App, Line, Tag};
+
+const COUNT_LIMIT: usize = 9999;
+const JUMPS: usize = 100;
+
+pub enum TagFilter {
+    Any,
+    Active,
+    Overlap,
+}
+
+pub enum TextObject {
+    Word,
+    Sentence,
+    Paragraph,
+    Quote,
+}
 
 impl App {
     pub fn common_colon(&mut self) {
+        if self.is_visual_mode() {
+            self.command.set("'<,'>");
+        }
+
         self.set_command_mode();
         self.change |= 0b_0010_0000;
     }
 
     pub fn common_t(&mut self) {
         self.tag();
-        self.set_normal_mode();
+        self.leave_visual_mode();
+    }
+
+    pub fn common_zz(&mut self) {
+        handle_zz(self);
+    }
+
+    pub fn common_zt(&mut self) {
+        handle_zt(self);
+    }
+
+    pub fn common_zb(&mut self) {
+        handle_zb(self);
+    }
+
+    pub fn common_digit(&mut self, c: char) {
+        let digit = c.to_digit(10).expect("Error parsing count digit") as usize;
+
+        self.count = std::cmp::min(self.count * 10 + digit, COUNT_LIMIT);
+        self.change |= 0b0001;
     }
+
+    pub fn common_repeat(&mut self, action: impl Fn(&mut App)) {
+        let count = self.count.clamp(1, COUNT_LIMIT);
+        let mode = self.mode.clone();
+
+        if self.count > 0 {
+            self.count = 0;
+            self.change |= 0b0001;
+        }
+
+        for _ in 0..count {
+            let position = get_repeat_position(self);
+
+            action(self);
+
+            if self.mode != mode || get_repeat_position(self) == position {
+                break;
+            }
+        }
+    }
+}
+
+/// Cursor, scroll and selection state, which a repeated action has to change to keep going.
+fn get_repeat_position(app: &App) -> (usize, usize, usize, Option<(usize, usize)>) {
+    let cursor = app.get_absolute_position(app.cursor_row + app.offset_row, app.cursor_column);
+    (cursor, app.offset_row, app.offset_column, app.get_visual_range())
 }
 
 #[allow(non_snake_case)]
@@ -37,32 +102,38 @@ pub fn handle_E(app: &mut App) {
 
 #[allow(non_snake_case)]
 pub fn handle_H(app: &mut App) {
+    let column = get_desired_column(app);
+
     if app.cursor_row > 0 {
         app.cursor_row = 0;
         app.change |= 0b1001;
-        manage_vertical_drift(app);
+        manage_desired_column(app, column);
     }
 }
 
 #[allow(non_snake_case)]
 pub fn handle_L(app: &mut App) {
+    let column = get_desired_column(app);
+
     let cursor_row = std::cmp::min(app.window_height.saturating_sub(2), app.nlines.saturating_sub(2));
 
     if app.cursor_row != cursor_row {
         app.cursor_row = cursor_row;
         app.change |= 0b1001;
-        manage_vertical_drift(app);
+        manage_desired_column(app, column);
     }
 }
 
 #[allow(non_snake_case)]
 pub fn handle_M(app: &mut App) {
+    let column = get_desired_column(app);
+
     let cursor_row = std::cmp::min(app.window_height / 2, app.nlines / 2);
 
     if app.cursor_row != cursor_row {
         app.cursor_row = cursor_row;
         app.change |= 0b1001;
-        manage_vertical_drift(app);
+        manage_desired_column(app, column);
     }
 }
 
@@ -85,6 +156,8 @@ pub fn handle_S(app: &mut App) {
 }
 
 pub fn handle_pg_down(app: &mut App) {
+    let column = get_desired_column(app);
+
     let offset_row = std::cmp::min(
         app.offset_row + app.cursor_row,
         app.nlines.saturating_sub(app.window_height).saturating_sub(1),
@@ -101,10 +174,12 @@ pub fn handle_pg_down(app: &mut App) {
         app.change |= 0b1001;
     }
 
-    manage_vertical_drift(app);
+    manage_desired_column(app, column);
 }
 
 pub fn handle_pg_up(app: &mut App) {
+    let column = get_desired_column(app);
+
     let offset_row = app
         .offset_row
         .saturating_sub(app.window_height.saturating_sub(app.cursor_row).saturating_sub(2));
@@ -120,7 +195,85 @@ pub fn handle_pg_up(app: &mut App) {
         app.change |= 0b1001;
     }
 
-    manage_vertical_drift(app);
+    manage_desired_column(app, column);
+}
+
+pub fn handle_scroll_down(app: &mut App, n: usize) {
+    let column = get_desired_column(app);
+
+    let offset_row = std::cmp::min(
+        app.offset_row + n,
+        app.nlines.saturating_sub(app.window_height.saturating_sub(1)),
+    );
+    let cursor_row = match offset_row - app.offset_row {
+        0 => app.cursor_row,
+        n => std::cmp::max(app.cursor_row.saturating_sub(n), get_scrolloff(app)),
+    };
+
+    if app.offset_row != offset_row {
+        app.offset_row = offset_row;
+        app.change |= 0b0011;
+    }
+
+    if app.cursor_row != cursor_row {
+        app.cursor_row = cursor_row;
+        app.change |= 0b1001;
+    }
+
+    manage_desired_column(app, column);
+}
+
+pub fn handle_scroll_up(app: &mut App, n: usize) {
+    let column = get_desired_column(app);
+
+    let offset_row = app.offset_row.saturating_sub(n);
+    let cursor_row = match app.offset_row - offset_row {
+        0 => app.cursor_row,
+        n => std::cmp::min(
+            app.cursor_row + n,
+            app.window_height.saturating_sub(2 + get_scrolloff(app)),
+        ),
+    };
+
+    if app.offset_row != offset_row {
+        app.offset_row = offset_row;
+        app.change |= 0b0011;
+    }
+
+    if app.cursor_row != cursor_row {
+        app.cursor_row = cursor_row;
+        app.change |= 0b1001;
+    }
+
+    manage_desired_column(app, column);
+}
+
+pub fn handle_zz(app: &mut App) {
+    move_to_center(app, app.cursor_row + app.offset_row, app.cursor_column);
+}
+
+pub fn handle_zt(app: &mut App) {
+    let row = app.cursor_row + app.offset_row;
+    scroll_to(app, row.saturating_sub(get_scrolloff(app)));
+}
+
+pub fn handle_zb(app: &mut App) {
+    let row = app.cursor_row + app.offset_row;
+    scroll_to(app, (row + get_scrolloff(app) + 2).saturating_sub(app.window_height));
+}
+
+fn scroll_to(app: &mut App, offset_row: usize) {
+    let row = app.cursor_row + app.offset_row;
+
+    if app.offset_row != offset_row {
+        app.offset_row = offset_row;
+        app.change |= 0b0011;
+    }
+
+    if app.cursor_row != row - offset_row {
+        app.cursor_row = row - offset_row;
+        app.change |= 0b1001;
+    }
 }
 
 pub fn handle_h(app: &mut App) {
@@ -139,6 +292,8 @@ pub fn handle_h(app: &mut App) {
 }
 
 pub fn handle_j(app: &mut App) {
+    let column = get_desired_column(app);
+
     if app.cursor_row + app.offset_row < app.nlines.saturating_sub(1) {
         if app.cursor_row < app.window_height.saturating_sub(2) {
             app.cursor_row += 1;
@@ -149,16 +304,12 @@ pub fn handle_j(app: &mut App) {
         }
     }
 
-    if app.change & 0b1010 > 0 {
-        let is_new = app.get_current_line().is_virtual;
-        let is_old = app.lines[(app.cursor_row + app.offset_row).saturating_sub(1)].is_virtual;
-        manage_virtual_shift(app, is_new, is_old);
-    }
-
-    manage_vertical_drift(app);
+    manage_desired_column(app, column);
 }
 
 pub fn handle_k(app: &mut App) {
+    let column = get_desired_column(app);
+
     if app.cursor_row > 0 {
         app.cursor_row -= 1;
         app.change |= 0b1001;
@@ -167,82 +318,202 @@ pub fn handle_k(app: &mut App) {
         app.change |= 0b0011;
     }
 
-    if app.change & 0b1010 > 0 {
-        let is_new = app.get_current_line().is_virtual;
-        let is_old = app.lines[app.cursor_row + app.offset_row + 1].is_virtual;
-        manage_virtual_shift(app, is_new, is_old);
-    }
-
-    manage_vertical_drift(app);
+    manage_desired_column(app, column);
 }
 
 pub fn handle_l(app: &mut App) {
-    if app.cursor_column < app.get_current_line_width().saturating_sub(1)
-        && app.cursor_column < app.window_width.saturating_sub(1)
-    {
+    if app.cursor_column < app.get_current_line_width().saturating_sub(1) {
         app.cursor_column += 1;
         app.change |= 0b0101;
-    } else if app.cursor_row < app.window_height.saturating_sub(2) {
-        app.cursor_column = 0;
-        app.cursor_row += 1;
-        app.change |= 0b1101;
     } else if app.cursor_row + app.offset_row < app.nlines.saturating_sub(1) {
         app.cursor_column = 0;
-        app.offset_row += 1;
-        app.change |= 0b1011;
+
+        if app.cursor_row < app.window_height.saturating_sub(2) {
+            app.cursor_row += 1;
+            app.change |= 0b1101;
+        } else {
+            app.offset_row += 1;
+            app.change |= 0b1011;
+        }
     }
 }
 
-pub fn handle_w(app: &mut App) {
-    let mut line_iter = app
-        .get_current_line()
-        .text
-        .chars()
-        .skip(app.cursor_column + 1)
-        .peekable();
+pub fn handle_w(app: &mut App, is_big: bool) {
+    let start = (app.cursor_row + app.offset_row, app.cursor_column);
+    let class = get_char_class(app, start, is_big);
+    let mut position = start;
 
-    if let Some(next) = line_iter.peek() {
-        let offset = if next.is_whitespace() {
-            line_iter.take_while(|x| x.is_whitespace()).collect::<Vec<_>>().len() + 1
-        } else {
-            line_iter.take_while(|x| !x.is_whitespace()).collect::<Vec<_>>().len()
-        };
+    if class != 0 {
+        while let Some(next) = step_forward(app, position) {
+            position = next;
 
-        app.cursor_column = std::cmp::min(app.cursor_column + offset, app.get_current_line_width() - 1);
-        app.change |= 0b0101;
-    } else if app.cursor_row < std::cmp::min(app.window_height - 2, app.nlines) {
-        app.cursor_row += 1;
-        app.cursor_column = 0;
-        app.change |= 0b1101;
-    } else if app.cursor_row + app.offset_row < app.nlines - 1 {
-        app.offset_row += 1;
-        app.cursor_column = 0;
-        app.change |= 0b0111;
+            if get_char_class(app, position, is_big) != class {
+                break;
+            }
+        }
     }
+
+    while get_char_class(app, position, is_big) == 0 && !(position != start && is_empty_line(app, position)) {
+        match step_forward(app, position) {
+            Some(next) => position = next,
+            None => break,
+        }
+    }
+
+    move_to_word(app, position);
 }
 
-pub fn handle_b(app: &mut App) {
-    let line = app.get_current_line();
-    let mut line_iter = line.text.chars().rev().skip(line.width - app.cursor_column).peekable();
+pub fn handle_e(app: &mut App, is_big: bool) {
+    let Some(mut position) = step_forward(app, (app.cursor_row + app.offset_row, app.cursor_column)) else {
+        return;
+    };
 
-    if let Some(next) = line_iter.peek() {
-        let offset = if next.is_whitespace() {
-            line_iter.take_while(|x| x.is_whitespace()).collect::<Vec<_>>().len() + 1
-        } else {
-            line_iter.take_while(|x| !x.is_whitespace()).collect::<Vec<_>>().len()
-        };
+    while get_char_class(app, position, is_big) == 0 {
+        match step_forward(app, position) {
+            Some(next) => position = next,
+            None => break,
+        }
+    }
 
-        app.cursor_column -= offset;
-        app.change |= 0b0101;
-    } else if app.cursor_row > 0 {
-        app.cursor_row = app.cursor_row.saturating_sub(1);
-        app.cursor_column = app.get_current_line_width().saturating_sub(1);
-        app.change |= 0b1101;
-    } else if app.offset_row > 0 {
-        app.offset_row = app.offset_row.saturating_sub(1);
-        app.cursor_column = app.get_current_line_width().saturating_sub(1);
-        app.change |= 0b0111;
+    let class = get_char_class(app, position, is_big);
+
+    while let Some(next) = step_forward(app, position).filter(|x| get_char_class(app, *x, is_big) == class) {
+        position = next;
+    }
+
+    move_to_word(app, position);
+}
+
+pub fn handle_b(app: &mut App, is_big: bool) {
+    let Some(mut position) = step_backward(app, (app.cursor_row + app.offset_row, app.cursor_column)) else {
+        return;
+    };
+
+    while get_char_class(app, position, is_big) == 0 && !is_empty_line(app, position) {
+        match step_backward(app, position) {
+            Some(prev) => position = prev,
+            None => break,
+        }
+    }
+
+    let class = get_char_class(app, position, is_big);
+
+    if class != 0 {
+        while let Some(prev) = step_backward(app, position).filter(|x| get_char_class(app, *x, is_big) == class) {
+            position = prev;
+        }
+    }
+
+    move_to_word(app, position);
+}
+
+pub fn handle_ge(app: &mut App, is_big: bool) {
+    let start = (app.cursor_row + app.offset_row, app.cursor_column);
+    let class = get_char_class(app, start, is_big);
+    let mut position = start;
+
+    if class != 0 {
+        while let Some(prev) = step_backward(app, position) {
+            position = prev;
+
+            if get_char_class(app, position, is_big) != class {
+                break;
+            }
+        }
+
+        if position == start || get_char_class(app, position, is_big) == class {
+            return;
+        }
+    }
+
+    while get_char_class(app, position, is_big) == 0 && !(position != start && is_empty_line(app, position)) {
+        match step_backward(app, position) {
+            Some(prev) => position = prev,
+            None => break,
+        }
     }
+
+    move_to_word(app, position);
+}
+
+fn get_char_class(app: &App, position: (usize, usize), is_big: bool) -> u8 {
+    let (row, column) = position;
+
+    app.lines[row]
+        .text
+        .get(column..)
+        .and_then(|x| x.chars().next())
+        .map_or(0, |c| classify_char(c, is_big))
+}
+
+fn classify_char(c: char, is_big: bool) -> u8 {
+    match c {
+        c if c.is_whitespace() => 0,
+        _ if is_big => 1,
+        c if c.is_alphanumeric() || c == '_' => 2,
+        _ => 1,
+    }
+}
+
+fn is_empty_line(app: &App, position: (usize, usize)) -> bool {
+    app.lines[position.0].width == 0
+}
+
+fn step_forward(app: &App, position: (usize, usize)) -> Option<(usize, usize)> {
+    let (row, column) = position;
+    let line = &app.lines[row];
+    let is_line_end = app.lines.get(row + 1).is_none_or(|x| !x.is_virtual);
+    let next = column
+        + line
+            .text
+            .get(column..)
+            .and_then(|x| x.chars().next())
+            .map_or(1, char::len_utf8);
+
+    if next < line.width || (next == line.width && is_line_end) {
+        Some((row, next))
+    } else if row + 1 < app.nlines {
+        Some((row + 1, 0))
+    } else {
+        None
+    }
+}
+
+fn step_backward(app: &App, position: (usize, usize)) -> Option<(usize, usize)> {
+    let (row, column) = position;
+
+    if column > 0 {
+        Some((row, get_prev_column(&app.lines[row], column)))
+    } else if row > 0 && app.lines[row].is_virtual {
+        Some((row - 1, get_prev_column(&app.lines[row - 1], app.lines[row - 1].width)))
+    } else if row > 0 {
+        Some((row - 1, app.lines[row - 1].width))
+    } else {
+        None
+    }
+}
+
+/// Byte column of the character before `column`, so word motions never stop inside a multibyte character.
+fn get_prev_column(line: &Line, column: usize) -> usize {
+    let width = line
+        .text
+        .get(..column)
+        .and_then(|x| x.chars().next_back())
+        .map_or(1, char::len_utf8);
+    column.saturating_sub(width)
+}
+
+fn move_to_word(app: &mut App, position: (usize, usize)) {
+    let (row, column) = position;
+    let line = &app.lines[row];
+
+    let column = if column < line.width {
+        column
+    } else {
+        get_prev_column(line, line.width)
+    };
+
+    move_to_position(app, row, column);
 }
 
 pub fn handle_s(app: &mut App) {
@@ -250,40 +521,504 @@ pub fn handle_s(app: &mut App) {
     app.change |= 0b0100;
 }
 
-pub fn handle_e(app: &mut App) {
-    app.cursor_column = std::cmp::min(
-        app.get_current_line_width().saturating_sub(1),
-        app.window_width.saturating_sub(1),
-    );
+pub fn handle_dollar(app: &mut App) {
+    app.cursor_column = app.get_current_line_width().saturating_sub(1);
     app.change |= 0b0100;
 }
 
-fn manage_vertical_drift(app: &mut App) {
-    if app.cursor_column > app.get_current_line_width().saturating_sub(1) {
-        app.cursor_column = app.get_current_line_width().saturating_sub(1);
-        app.change |= 0b0101;
+pub fn handle_find(app: &mut App, kind: char) {
+    app.find = Some((kind, app.argument));
+    find_char(app, kind, app.argument);
+}
+
+pub fn handle_find_repeat(app: &mut App, is_reverse: bool) {
+    if let Some((kind, target)) = app.find {
+        let kind = match (kind, is_reverse) {
+            ('f', true) => 'F',
+            ('F', true) => 'f',
+            ('t', true) => 'T',
+            ('T', true) => 't',
+            (kind, _) => kind,
+        };
+
+        find_char(app, kind, target);
     }
 }
 
-fn manage_virtual_shift(app: &mut App, is_new: bool, is_old: bool) {
-    if is_new != is_old {
-        if is_new {
-            app.cursor_column = app.cursor_column.saturating_sub(2);
-        } else {
-            app.cursor_column += 2;
+fn find_char(app: &mut App, kind: char, target: char) {
+    let row = app.cursor_row + app.offset_row;
+    let absolute_row = app.lines[row].absolute_row;
+    let first = row
+        - app.lines[..row]
+            .iter()
+            .rev()
+            .take_while(|x| x.absolute_row == absolute_row)
+            .count();
+
+    let positions = app.lines[first..]
+        .iter()
+        .enumerate()
+        .take_while(|(_, x)| x.absolute_row == absolute_row)
+        .flat_map(|(i, x)| x.text.char_indices().map(move |(column, c)| (first + i, column, c)))
+        .collect::<Vec<(usize, usize, char)>>();
+
+    let Some(current) = positions
+        .iter()
+        .position(|&(r, column, _)| r == row && column == app.cursor_column)
+    else {
+        return;
+    };
+
+    let is_target = |x: &(usize, usize, char)| x.2 == target;
+    let found = match kind {
+        'f' => positions[current + 1..]
+            .iter()
+            .position(is_target)
+            .map(|x| current + 1 + x),
+        't' => positions
+            .get(current + 2..)
+            .and_then(|x| x.iter().position(is_target))
+            .map(|x| current + 1 + x),
+        'F' => positions[..current].iter().rposition(is_target),
+        'T' => positions[..current.saturating_sub(1)]
+            .iter()
+            .rposition(is_target)
+            .map(|x| x + 1),
+        _ => None,
+    };
+
+    if let Some((row, column, _)) = found.map(|x| positions[x]) {
+        move_to_position(app, row, column);
+    }
+}
+
+pub fn find_text_object(app: &App, position: usize, object: TextObject, is_inner: bool) -> Option<(usize, usize)> {
+    let text = app.get_text();
+
+    let (start, end) = match object {
+        TextObject::Word => find_word(&text, position, is_inner),
+        TextObject::Sentence => {
+            let (start, end) = *app.sentences.iter().find(|x| position < x.1)?;
+            Some(if is_inner {
+                (start, end)
+            } else {
+                extend_blank(&text, start, end)
+            })
+        }
+        TextObject::Paragraph => find_paragraph(&text, position),
+        TextObject::Quote => find_quote(&text, position, is_inner),
+    }?;
+
+    (end > start).then_some((start, end))
+}
+
+fn find_word(text: &str, position: usize, is_inner: bool) -> Option<(usize, usize)> {
+    let class = text
+        .get(position..)?
+        .chars()
+        .next()
+        .filter(|x| *x != '\n')
+        .map(|x| classify_char(x, false))?;
+    let is_same = |x: char| x != '\n' && classify_char(x, false) == class;
+
+    let start = skip_backward(text, position, is_same);
+    let end = skip_forward(text, position, is_same);
+
+    if is_inner {
+        Some((start, end))
+    } else if class == 0 {
+        let class = text[end..]
+            .chars()
+            .next()
+            .filter(|x| *x != '\n')
+            .map(|x| classify_char(x, false));
+        let end = class.map_or(end, |class| {
+            skip_forward(text, end, |x| classify_char(x, false) == class)
+        });
+
+        Some((start, end))
+    } else {
+        Some(extend_blank(text, start, end))
+    }
+}
+
+fn find_paragraph(text: &str, position: usize) -> Option<(usize, usize)> {
+    let is_blank = |x: &(usize, &str)| x.1.trim().is_empty();
+    let lines = text
+        .split('\n')
+        .scan(0, |offset, x| {
+            let line = (*offset, x);
+            *offset += x.len() + 1;
+            Some(line)
+        })
+        .collect::<Vec<(usize, &str)>>();
+
+    let row = lines.partition_point(|x| x.0 <= position).checked_sub(1)?;
+
+    if is_blank(&lines[row]) {
+        return None;
+    }
+
+    let first = lines[..row].iter().rposition(is_blank).map_or(0, |x| x + 1);
+    let last = lines[row..].iter().position(is_blank).map_or(lines.len(), |x| row + x) - 1;
+
+    Some((lines[first].0, lines[last].0 + lines[last].1.len()))
+}
+
+fn find_quote(text: &str, position: usize, is_inner: bool) -> Option<(usize, usize)> {
+    let start = text[..position].rfind('\n').map_or(0, |x| x + 1);
+    let end = text[position..].find('\n').map_or(text.len(), |x| position + x);
+
+    let quotes = text[start..end]
+        .match_indices('"')
+        .map(|(x, _)| start + x)
+        .collect::<Vec<usize>>();
+    let (open, close) = quotes
+        .chunks_exact(2)
+        .map(|x| (x[0], x[1]))
+        .find(|&(_, close)| position <= close)?;
+
+    Some(if is_inner { (open + 1, close) } else { (open, close + 1) })
+}
+
+fn extend_blank(text: &str, start: usize, end: usize) -> (usize, usize) {
+    let is_blank = |x: char| x != '\n' && x.is_whitespace();
+    let trailing = skip_forward(text, end, is_blank);
+
+    if trailing > end {
+        (start, trailing)
+    } else {
+        (skip_backward(text, start, is_blank), end)
+    }
+}
+
+fn skip_forward(text: &str, position: usize, predicate: impl Fn(char) -> bool) -> usize {
+    position
+        + text[position..]
+            .chars()
+            .take_while(|x| predicate(*x))
+            .map(char::len_utf8)
+            .sum::<usize>()
+}
+
+fn skip_backward(text: &str, position: usize, predicate: impl Fn(char) -> bool) -> usize {
+    position
+        - text[..position]
+            .chars()
+            .rev()
+            .take_while(|x| predicate(*x))
+            .map(char::len_utf8)
+            .sum::<usize>()
+}
+
+#[test]
+fn test_find_text_object() {
+    let text = "say \"U.S.-based  firm\"\n\nnext";
+
+    assert!(find_word(text, 5, true) == Some((5, 6)));
+    assert!(find_word(text, 12, false) == Some((10, 17)));
+    assert!(find_word(text, 16, false) == Some((15, 21)));
+    assert!(find_word(text, 22, true).is_none());
+    assert!(find_quote(text, 0, true) == Some((5, 21)));
+    assert!(find_quote(text, 21, false) == Some((4, 22)));
+    assert!(find_paragraph(text, 7) == Some((0, 22)));
+    assert!(find_paragraph(text, 23).is_none());
+    assert!(find_paragraph(text, 24) == Some((24, 28)));
+}
+
+pub fn handle_tag_next(app: &mut App, filter: TagFilter) {
+    let cursor = (app.cursor_row + app.offset_row, app.cursor_column);
+
+    if let Some((row, column)) = get_tag_starts(app, filter).into_iter().find(|&x| x > cursor) {
+        move_to_position(app, row, column);
+    }
+}
+
+pub fn handle_tag_prev(app: &mut App, filter: TagFilter) {
+    let cursor = (app.cursor_row + app.offset_row, app.cursor_column);
+
+    if let Some((row, column)) = get_tag_starts(app, filter).into_iter().rev().find(|&x| x < cursor) {
+        move_to_position(app, row, column);
+    }
+}
+
+fn get_tag_starts(app: &App, filter: TagFilter) -> Vec<(usize, usize)> {
+    let is_overlapping = |tags: &[Tag], tag: &Tag| {
+        tags.iter()
+            .filter(|x| app.labels[x.label].is_visible && !std::ptr::eq(*x, tag))
+            .any(|x| x.start < tag.end && tag.start < x.end)
+    };
+
+    app.get_span_heads()
+        .into_iter()
+        .filter(|&(row, position)| {
+            let tag = &app.lines[row].tags[position];
+
+            app.labels[tag.label].is_visible
+                && match filter {
+                    TagFilter::Any => true,
+                    TagFilter::Active => tag.label == app.modal_active,
+                    TagFilter::Overlap => app.get_span(row, position).into_iter().any(|(row, position)| {
+                        let tags = &app.lines[row].tags;
+                        is_overlapping(tags, &tags[position])
+                    }),
+                }
+        })
+        .map(|(row, position)| (row, app.lines[row].tags[position].start))
+        .collect()
+}
+
+#[test]
+fn test_tag_jumps() {
+    let ents = [(0, 5, "PER"), (11, 22, "LOC"), (17, 22, "PER")];
+    let mut app = crate::io::load_test_app("alpha beta gamma\ndelta epsilon", &ents);
+
+    handle_tag_next(&mut app, TagFilter::Any);
+    assert!((app.cursor_row, app.cursor_column) == (0, 11));
+    handle_tag_next(&mut app, TagFilter::Any);
+    assert!((app.cursor_row, app.cursor_column) == (1, 0));
+    handle_tag_prev(&mut app, TagFilter::Active);
+    assert!((app.cursor_row, app.cursor_column) == (0, 0));
+    handle_tag_next(&mut app, TagFilter::Overlap);
+    assert!((app.cursor_row, app.cursor_column) == (0, 11));
+    handle_tag_next(&mut app, TagFilter::Active);
+    assert!((app.cursor_row, app.cursor_column) == (1, 0));
+}
+
+pub fn push_jump(app: &mut App) {
+    let position = app.get_absolute_position(app.cursor_row + app.offset_row, app.cursor_column);
+
+    app.jumps.truncate(app.jump_index);
+
+    if app.jumps.last() != Some(&position) {
+        app.jumps.push(position);
+    }
+
+    if app.jumps.len() > JUMPS {
+        app.jumps.remove(0);
+    }
+
+    app.jump_index = app.jumps.len();
+}
+
+pub fn handle_jump_older(app: &mut App) {
+    if app.jump_index >= app.jumps.len() {
+        let position = app.get_absolute_position(app.cursor_row + app.offset_row, app.cursor_column);
+
+        if app.jumps.last() != Some(&position) {
+            app.jumps.push(position);
         }
+
+        app.jump_index = app.jumps.len() - 1;
+    }
+
+    if app.jump_index > 0 {
+        app.jump_index -= 1;
+        move_to_absolute(app, app.jumps[app.jump_index]);
+    }
+}
+
+pub fn handle_jump_newer(app: &mut App) {
+    if app.jump_index + 1 < app.jumps.len() {
+        app.jump_index += 1;
+        move_to_absolute(app, app.jumps[app.jump_index]);
+    }
+}
+
+pub fn move_to_absolute(app: &mut App, position: usize) {
+    let (row, column) = app.find_absolute_position(position);
+    move_to_position(app, row, column);
+}
+
+pub fn move_to_position(app: &mut App, row: usize, column: usize) {
+    let offset_row = if row < app.offset_row {
+        row
+    } else if row > app.offset_row + app.window_height.saturating_sub(2) {
+        row - app.window_height.saturating_sub(2)
+    } else {
+        app.offset_row
+    };
+
+    if app.offset_row != offset_row {
+        app.offset_row = offset_row;
+        app.change |= 0b0011;
+    }
+
+    if app.cursor_row != row - app.offset_row {
+        app.cursor_row = row - app.offset_row;
+        app.change |= 0b1001;
+    }
+
+    if app.cursor_column != column {
+        app.cursor_column = column;
         app.change |= 0b0101;
     }
 }
 
-fn move_to_line_end(app: &mut App) {
+pub fn move_to_center(app: &mut App, row: usize, column: usize) {
+    let offset_row = std::cmp::min(
+        row.saturating_sub(app.window_height.saturating_sub(1) / 2),
+        app.nlines.saturating_sub(app.window_height.saturating_sub(1)),
+    );
+
+    if app.offset_row != offset_row {
+        app.offset_row = offset_row;
+        app.change |= 0b0011;
+    }
+
+    if app.cursor_row != row - app.offset_row {
+        app.cursor_row = row - app.offset_row;
+        app.change |= 0b1001;
+    }
+
+    if app.cursor_column != column {
+        app.cursor_column = column;
+        app.change |= 0b0101;
+    }
+}
+
+pub fn manage_scrolloff(app: &mut App) {
+    let row = app.cursor_row + app.offset_row;
+    let scrolloff = get_scrolloff(app);
+    let bottom = std::cmp::min(scrolloff, app.nlines.saturating_sub(row + 1));
+
+    let offset_row = std::cmp::max(
+        std::cmp::min(app.offset_row, row.saturating_sub(scrolloff)),
+        (row + bottom + 2).saturating_sub(app.window_height),
+    );
+
+    scroll_to(app, offset_row);
+}
+
+fn get_scrolloff(app: &App) -> usize {
+    std::cmp::min(app.settings.scrolloff, app.window_height.saturating_sub(2) / 2)
+}
+
+pub fn manage_horizontal_scroll(app: &mut App) {
+    let width = app.window_width.saturating_sub(app.get_left_margin() + 1);
+
+    let mut offset_column = if !app.settings.is_overflow {
+        0
+    } else if app.cursor_column < app.offset_column {
+        app.cursor_column
+    } else if app.cursor_column >= app.offset_column + width {
+        app.cursor_column + 1 - width
+    } else {
+        app.offset_column
+    };
+
+    let text = &app.get_current_line().text;
+
+    while offset_column > 0 && !text.is_char_boundary(offset_column) {
+        offset_column -= 1;
+    }
+
+    if app.offset_column != offset_column {
+        app.offset_column = offset_column;
+        app.change |= 0b0011;
+    }
+}
+
+fn get_desired_column(app: &App) -> usize {
+    match app.desired_column {
+        Some((column, row, cursor_column))
+            if row == app.cursor_row + app.offset_row && cursor_column == app.cursor_column =>
+        {
+            column
+        }
+        _ => app.cursor_column + if app.get_current_line().is_virtual { 2 } else { 0 },
+    }
+}
+
+fn manage_desired_column(app: &mut App, column: usize) {
+    let shift = if app.get_current_line().is_virtual { 2 } else { 0 };
     let cursor_column = std::cmp::min(
+        column.saturating_sub(shift),
         app.get_current_line_width().saturating_sub(1),
-        app.window_width.saturating_sub(1),
     );
 
+    if app.cursor_column != cursor_column {
+        app.cursor_column = cursor_column;
+        app.change |= 0b0101;
+    }
+
+    app.desired_column = Some((column, app.cursor_row + app.offset_row, app.cursor_column));
+}
+
+fn move_to_line_end(app: &mut App) {
+    let cursor_column = app.get_current_line_width().saturating_sub(1);
+
     if app.cursor_column != cursor_column {
         app.cursor_column = cursor_column;
         app.change |= 0b0100;
     }
 }
+
+#[test]
+fn test_repeat_limit() {
+    let mut app = crate::io::load_test_app("alpha beta gamma", &[]);
+
+    app.normal_v();
+    "99999999999".chars().for_each(|x| app.common_digit(x));
+    assert!(app.count == COUNT_LIMIT);
+
+    app.common_repeat(|app| app.visual_l());
+    assert!(app.count == 0 && app.cursor_column == 15);
+    assert!(app.get_visual_range() == Some((0, 16)));
+}
+
+#[test]
+fn test_word_motions() {
+    let mut app = crate::io::load_test_app("foo.bar  baz-qux\n\nnaïve café", &[]);
+    let mut walk = |start: (usize, usize), motion: fn(&mut App), n: usize| {
+        (app.cursor_row, app.cursor_column) = start;
+
+        (0..n)
+            .map(|_| {
+                motion(&mut app);
+                (app.cursor_row, app.cursor_column)
+            })
+            .collect::<Vec<(usize, usize)>>()
+    };
+
+    assert!(walk((0, 0), App::normal_w, 8) == [(0, 3), (0, 4), (0, 9), (0, 12), (0, 13), (1, 0), (2, 0), (2, 7)]);
+    assert!(walk((0, 0), App::normal_W, 4) == [(0, 9), (1, 0), (2, 0), (2, 7)]);
+    assert!(walk((0, 0), App::normal_e, 8) == [(0, 2), (0, 3), (0, 6), (0, 11), (0, 12), (0, 15), (2, 5), (2, 10)]);
+    assert!(walk((0, 0), App::normal_end_WORD, 4) == [(0, 6), (0, 15), (2, 5), (2, 10)]);
+    assert!(walk((2, 7), App::normal_b, 8) == [(2, 0), (1, 0), (0, 13), (0, 12), (0, 9), (0, 4), (0, 3), (0, 0)]);
+    assert!(walk((2, 7), App::normal_B, 4) == [(2, 0), (1, 0), (0, 9), (0, 0)]);
+    assert!(walk((2, 10), App::normal_e, 1) == [(2, 10)]);
+    assert!(walk((2, 7), App::normal_ge, 3) == [(2, 5), (1, 0), (0, 15)]);
+}
+
+#[test]
+fn test_marks_and_jumps() {
+    fn press(app: &mut App, keys: &str) -> (usize, usize) {
+        keys.chars().for_each(|x| app.dispatch(x));
+        (app.cursor_row + app.offset_row, app.cursor_column)
+    }
+
+    let mut app = crate::io::load_test_app("one\ntwo\nthree", &[(0, 3, "PER")]);
+
+    assert!(press(&mut app, "jlma") == (1, 1));
+    assert!(press(&mut app, "G") == (2, 4));
+    assert!(press(&mut app, "'a") == (1, 1));
+    assert!(press(&mut app, "gg") == (0, 0));
+    assert!(app.jumps == [5, 12, 5]);
+
+    assert!(press(&mut app, "\x0f") == (1, 1));
+    assert!(press(&mut app, "\x0f") == (2, 4));
+    assert!(press(&mut app, "\x09") == (1, 1));
+    assert!(press(&mut app, "G") == (2, 4));
+    assert!(app.jumps == [5, 12, 5]);
+
+    press(&mut app, "m1");
+    assert!(app.message == "Invalid mark: 1");
+    press(&mut app, "'b");
+    assert!(app.message == "Mark not set: b");
+
+    for _ in 0..JUMPS {
+        press(&mut app, "ggG");
+    }
+    assert!(app.jumps.len() == JUMPS);
+}
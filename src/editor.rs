@@ -0,0 +1,235 @@
+#[derive(Debug, Default)]
+pub struct LineEditor {
+    pub text: String,
+    pub cursor: usize,
+    completion: Option<(usize, String, usize)>,
+    draft: String,
+    history: Vec<String>,
+    history_index: usize,
+}
+
+impl LineEditor {
+    pub fn set(&mut self, text: &str) {
+        self.text = text.to_owned();
+        self.cursor = text.len();
+        self.completion = None;
+        self.history_index = self.history.len();
+    }
+
+    pub fn clear(&mut self) {
+        self.set("");
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    pub fn get_cursor_width(&self) -> usize {
+        self.text[..self.cursor].chars().count()
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+        self.completion = None;
+    }
+
+    /// Cuts the text down to `limit` characters, keeping the cursor inside it.
+    pub fn truncate(&mut self, limit: usize) {
+        if let Some((i, _)) = self.text.char_indices().nth(limit) {
+            self.text.truncate(i);
+            self.cursor = std::cmp::min(self.cursor, i);
+        }
+    }
+
+    pub fn backspace(&mut self) -> bool {
+        let start = self.get_prev_boundary();
+        self.delete_to(start)
+    }
+
+    pub fn left(&mut self) -> bool {
+        let cursor = self.get_prev_boundary();
+        self.move_to(cursor)
+    }
+
+    pub fn right(&mut self) -> bool {
+        let cursor = self.cursor + self.text[self.cursor..].chars().next().map_or(0, char::len_utf8);
+        self.move_to(cursor)
+    }
+
+    pub fn delete_line(&mut self) -> bool {
+        self.delete_to(0)
+    }
+
+    pub fn delete_word(&mut self) -> bool {
+        let get_class = |c: char| match c {
+            c if c.is_whitespace() => 0,
+            c if c.is_alphanumeric() || c == '_' => 2,
+            _ => 1,
+        };
+
+        let mut chars = self.text[..self.cursor].char_indices().rev().peekable();
+        let mut start = self.cursor;
+
+        while let Some((i, _)) = chars.next_if(|(_, x)| x.is_whitespace()) {
+            start = i;
+        }
+
+        if let Some(&(_, c)) = chars.peek() {
+            while let Some((i, _)) = chars.next_if(|(_, x)| get_class(*x) == get_class(c)) {
+                start = i;
+            }
+        }
+
+        self.delete_to(start)
+    }
+
+    pub fn history_prev(&mut self) -> bool {
+        if self.history_index == 0 {
+            return false;
+        }
+
+        if self.history_index == self.history.len() {
+            self.draft = self.text.clone();
+        }
+
+        self.history_index -= 1;
+        self.load_history();
+        true
+    }
+
+    pub fn history_next(&mut self) -> bool {
+        if self.history_index >= self.history.len() {
+            return false;
+        }
+
+        self.history_index += 1;
+        self.load_history();
+        true
+    }
+
+    pub fn push_history(&mut self) {
+        if !self.text.is_empty() && self.history.last() != Some(&self.text) {
+            self.history.push(self.text.clone());
+        }
+
+        self.history_index = self.history.len();
+    }
+
+    pub fn complete(&mut self, candidates: &[String]) -> bool {
+        let start = self.text[..self.cursor].rfind(' ').map_or(0, |x| x + 1);
+
+        let (word, index) = match self.completion.take() {
+            Some((s, word, index)) if s == start => (word, index + 1),
+            _ => (self.text[start..self.cursor].to_owned(), 0),
+        };
+
+        let matches = candidates
+            .iter()
+            .filter(|x| x.starts_with(&word))
+            .collect::<Vec<&String>>();
+
+        if matches.is_empty() {
+            return false;
+        }
+
+        let candidate = matches[index % matches.len()];
+
+        self.text.replace_range(start..self.cursor, candidate);
+        self.cursor = start + candidate.len();
+        self.completion = Some((start, word, index));
+        true
+    }
+
+    fn get_prev_boundary(&self) -> usize {
+        self.cursor - self.text[..self.cursor].chars().next_back().map_or(0, char::len_utf8)
+    }
+
+    fn move_to(&mut self, cursor: usize) -> bool {
+        self.completion = None;
+
+        if self.cursor == cursor {
+            return false;
+        }
+
+        self.cursor = cursor;
+        true
+    }
+
+    fn delete_to(&mut self, start: usize) -> bool {
+        self.completion = None;
+
+        if start == self.cursor {
+            return false;
+        }
+
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+        true
+    }
+
+    fn load_history(&mut self) {
+        let text = self.history.get(self.history_index).unwrap_or(&self.draft).clone();
+
+        self.text = text;
+        self.cursor = self.text.len();
+        self.completion = None;
+    }
+}
+
+#[test]
+fn test_delete_word() {
+    let mut editor = LineEditor::default();
+    editor.set("set textwidth=80  ");
+
+    assert!(editor.delete_word() && editor.text == "set textwidth=");
+    assert!(editor.delete_word() && editor.text == "set textwidth");
+    assert!(editor.delete_word() && editor.text == "set ");
+    assert!(editor.delete_word() && editor.text.is_empty());
+    assert!(!editor.delete_word());
+}
+
+#[test]
+fn test_complete() {
+    let mut editor = LineEditor::default();
+    let candidates = ["quit", "set", "scrolloff"].map(String::from);
+    editor.set("s");
+
+    assert!(editor.complete(&candidates) && editor.text == "set");
+    assert!(editor.complete(&candidates) && editor.text == "scrolloff");
+    assert!(editor.complete(&candidates) && editor.text == "set");
+    editor.insert('x');
+    assert!(!editor.complete(&candidates) && editor.text == "setx");
+}
+
+#[test]
+fn test_history() {
+    let mut editor = LineEditor::default();
+
+    for command in ["w", "q"] {
+        editor.set(command);
+        editor.push_history();
+    }
+
+    editor.set("dra");
+    editor.left();
+    editor.insert('é');
+
+    assert!(editor.text == "dréa" && editor.get_cursor_width() == 3);
+    assert!(editor.history_prev() && editor.text == "q");
+    assert!(editor.history_prev() && editor.text == "w");
+    assert!(!editor.history_prev());
+    assert!(editor.history_next() && editor.history_next() && editor.text == "dréa");
+}
+
+#[test]
+fn test_truncate() {
+    let mut editor = LineEditor::default();
+
+    editor.set("naïve_label");
+    editor.truncate(4);
+    assert!(editor.text == "naïv" && editor.cursor == 5);
+
+    editor.truncate(10);
+    assert!(editor.text == "naïv");
+}
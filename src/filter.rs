@@ -0,0 +1,68 @@
+use crate::app::{App, Mode};
+
+impl App {
+    pub fn is_filter_mode(&self) -> bool {
+        self.mode == Mode::Filter
+    }
+
+    pub fn set_filter_mode(&mut self) {
+        self.mode = Mode::Filter;
+    }
+
+    fn set_modal_filter(&mut self, is_changed: bool) {
+        if is_changed {
+            self.manage_modal_offset();
+            self.change |= 0b_0001_1000;
+        }
+    }
+
+    pub fn filter_char(&mut self, c: char) {
+        self.modal_filter.insert(c);
+        self.set_modal_filter(true);
+    }
+
+    pub fn filter_backspace(&mut self) {
+        let is_changed = self.modal_filter.backspace();
+        self.set_modal_filter(is_changed);
+    }
+
+    pub fn filter_left(&mut self) {
+        let is_changed = self.modal_filter.left();
+        self.set_modal_filter(is_changed);
+    }
+
+    pub fn filter_right(&mut self) {
+        let is_changed = self.modal_filter.right();
+        self.set_modal_filter(is_changed);
+    }
+
+    pub fn filter_delete_line(&mut self) {
+        let is_changed = self.modal_filter.delete_line();
+        self.set_modal_filter(is_changed);
+    }
+
+    pub fn filter_delete_word(&mut self) {
+        let is_changed = self.modal_filter.delete_word();
+        self.set_modal_filter(is_changed);
+    }
+
+    pub fn filter_down(&mut self) {
+        self.modal_j();
+    }
+
+    pub fn filter_up(&mut self) {
+        self.modal_k();
+    }
+
+    pub fn filter_return(&mut self) {
+        self.set_modal_mode();
+        self.change |= 0b_0001_1000;
+    }
+
+    pub fn filter_esc(&mut self) {
+        self.modal_filter.clear();
+        self.manage_modal_offset();
+        self.set_modal_mode();
+        self.change |= 0b_0001_1000;
+    }
+}
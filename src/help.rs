@@ -0,0 +1,198 @@
+use crate::{
+    app::{App, Mode},
+    command,
+    keymap::{self, Keymap},
+    settings::{self, Settings},
+};
+
+const MODES: &[Mode] = &[
+    Mode::Normal,
+    Mode::Visual,
+    Mode::Picker,
+    Mode::Review,
+    Mode::Modal,
+    Mode::Name,
+    Mode::Filter,
+    Mode::Command,
+    Mode::Help,
+];
+
+impl App {
+    pub fn is_help_mode(&self) -> bool {
+        self.mode == Mode::Help
+    }
+
+    pub fn set_help_mode(&mut self) {
+        self.mode = Mode::Help;
+    }
+
+    pub fn open_help(&mut self, query: &str) {
+        self.help_row = 0;
+        self.help_query = query.to_owned();
+        self.set_help_mode();
+
+        if !query.is_empty() {
+            self.help_seek(true, true);
+        }
+
+        self.change |= 0b_0100_1000;
+    }
+
+    /// Opens help scrolled to the current option values, as listed by `:set`.
+    pub fn open_options(&mut self) {
+        self.open_help("");
+
+        let lines = get_help_lines(&self.keymap, &self.settings);
+        self.scroll_help_to(lines.iter().position(|x| x == "Options").unwrap_or(0));
+    }
+
+    pub fn get_help_size(&self) -> (usize, usize) {
+        let width = std::cmp::min(64, self.window_width.saturating_sub(4));
+        let height = self.window_height.saturating_sub(3);
+
+        (width, height)
+    }
+
+    fn get_help_max_row(&self) -> usize {
+        let (_, height) = self.get_help_size();
+        get_help_lines(&self.keymap, &self.settings)
+            .len()
+            .saturating_sub(height.saturating_sub(2))
+    }
+
+    fn help_seek(&mut self, is_forward: bool, is_inclusive: bool) {
+        let query = self.help_query.to_lowercase();
+        let mut rows = get_help_lines(&self.keymap, &self.settings)
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| x.to_lowercase().contains(&query))
+            .map(|(i, _)| i)
+            .collect::<Vec<usize>>();
+
+        if !is_forward {
+            rows.reverse();
+        }
+
+        let next = rows
+            .iter()
+            .find(|&&x| match (is_forward, is_inclusive) {
+                (true, true) => x >= self.help_row,
+                (true, false) => x > self.help_row,
+                (false, true) => x <= self.help_row,
+                (false, false) => x < self.help_row,
+            })
+            .or(rows.first());
+
+        match next {
+            Some(&row) => self.help_row = std::cmp::min(row, self.get_help_max_row()),
+            None => self.message = format!("Pattern not found: {}", self.help_query),
+        }
+
+        self.change |= 0b_0100_0001;
+    }
+
+    fn scroll_help_to(&mut self, row: usize) {
+        let row = std::cmp::min(row, self.get_help_max_row());
+
+        if row != self.help_row {
+            self.help_row = row;
+            self.change |= 0b_0100_0000;
+        }
+    }
+
+    pub fn help_j(&mut self) {
+        self.scroll_help_to(self.help_row + 1);
+    }
+
+    pub fn help_k(&mut self) {
+        self.scroll_help_to(self.help_row.saturating_sub(1));
+    }
+
+    pub fn help_pg_down(&mut self) {
+        let (_, height) = self.get_help_size();
+        self.scroll_help_to(self.help_row + height.saturating_sub(2));
+    }
+
+    pub fn help_pg_up(&mut self) {
+        let (_, height) = self.get_help_size();
+        self.scroll_help_to(self.help_row.saturating_sub(height.saturating_sub(2)));
+    }
+
+    pub fn help_g(&mut self) {
+        self.scroll_help_to(0);
+    }
+
+    #[allow(non_snake_case)]
+    pub fn help_G(&mut self) {
+        self.scroll_help_to(usize::MAX);
+    }
+
+    pub fn help_slash(&mut self) {
+        self.command.set("help ");
+        self.set_command_mode();
+        self.change |= 0b_0010_0000;
+    }
+
+    pub fn help_n(&mut self) {
+        if !self.help_query.is_empty() {
+            self.help_seek(true, false);
+        }
+    }
+
+    #[allow(non_snake_case)]
+    pub fn help_N(&mut self) {
+        if !self.help_query.is_empty() {
+            self.help_seek(false, false);
+        }
+    }
+
+    pub fn help_q(&mut self) {
+        self.set_normal_mode();
+        self.change |= 0b_0000_1011;
+    }
+}
+
+pub fn get_help_lines(keymap: &Keymap, settings: &Settings) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for mode in MODES {
+        lines.push(format!("{:?} mode", mode));
+
+        let bindings = keymap
+            .bindings
+            .iter()
+            .filter(|x| &x.mode == mode)
+            .map(|x| (keymap::format_keys(&x.keys), x.action));
+
+        for (keys, action) in bindings {
+            lines.push(format!("  {:12} {}", keys, action));
+        }
+
+        lines.push(String::new());
+    }
+
+    lines.push("Commands".to_owned());
+    lines.extend(command::COMMANDS.iter().map(|x| format!("  :{}", x)));
+    lines.extend(settings::OPTIONS.iter().map(|x| format!("  :set {}", x)));
+
+    lines.push(String::new());
+    lines.push("Options".to_owned());
+    lines.extend(settings::format_options(settings).iter().map(|x| format!("  {}", x)));
+
+    lines
+}
+
+#[test]
+fn test_help_lines() {
+    let lines = get_help_lines(&Keymap::default(), &Settings::default());
+
+    assert!(lines.iter().any(|x| x.starts_with("  gg ") && x.ends_with(" normal_S")));
+    assert!(lines
+        .iter()
+        .any(|x| x.starts_with("  <C-n> ") && x.ends_with(" normal_pg_down")));
+    assert!(lines.iter().any(|x| x == "  :help"));
+    assert!(lines
+        .iter()
+        .skip_while(|x| *x != "Options")
+        .any(|x| x == "  textwidth=0"));
+}
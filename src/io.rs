@@ -1,22 +1,24 @@
 use std::fs::File;
 use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use crossterm::{style::Color, terminal};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
-use crate::app::{App, FType, Label, Line, Tag, COLORS};
+use crate::app::{App, FType, Label, Line, Span, Tag, COLORS};
+use crate::common;
+use crate::settings;
 use crate::Argv;
 
 #[derive(Debug, Deserialize, Serialize)]
 struct Spacy {
     text: String,
     ents: Vec<Ent>,
+    #[serde(default)]
+    sents: Vec<Sent>,
 
-    #[allow(dead_code)]
-    #[serde(skip)]
-    sents: Option<String>,
     #[allow(dead_code)]
     #[serde(skip)]
     tokens: Option<String>,
@@ -29,27 +31,54 @@ struct Ent {
     start: usize,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+struct Sent {
+    end: usize,
+    start: usize,
+}
+
 type Accumulator = (Vec<Line>, usize, usize);
 type Enumerate = (usize, String);
 type Pair = (usize, usize);
 
 #[allow(private_interfaces)]
 pub fn load_file(argv: &Argv) -> Result<App> {
-    let format = argv.format.clone().unwrap_or_else(|| {
-        if argv.name.ends_with(".tan") {
+    let mut app = open_file(&argv.name, argv.format.clone())?;
+
+    let config = config_path("config.toml");
+    settings::load(&mut app, &config).with_context(|| format!("Error loading {}", config.display()))?;
+
+    app.settings.text_width = argv.width.unwrap_or(app.settings.text_width);
+    app.settings.margin = argv.margin.unwrap_or(app.settings.margin);
+    app.settings.is_centered |= argv.center;
+
+    rewrap(&mut app);
+    Ok(app)
+}
+
+pub fn open_file(filename: &str, format: Option<FType>) -> Result<App> {
+    let format = format.unwrap_or_else(|| {
+        if filename.ends_with(".tan") {
             FType::Tan
-        } else if argv.name.ends_with(".json") {
+        } else if filename.ends_with(".json") {
             FType::Spacy
         } else {
             FType::Plain
         }
     });
 
-    match format {
-        FType::Plain => load_raw(&argv.name),
-        FType::Spacy => load_spacy(&argv.name),
-        FType::Tan => load_tan(&argv.name),
+    let mut app = match format {
+        FType::Plain => load_raw(filename),
+        FType::Spacy => load_spacy(filename),
+        FType::Tan => load_tan(filename),
+    }?;
+
+    if app.sentences.is_empty() {
+        app.sentences = split_sentences(&app.get_text());
     }
+
+    app.set_window(terminal::window_size()?);
+    Ok(app)
 }
 
 fn load_raw(filename: &str) -> Result<App> {
@@ -71,6 +100,8 @@ fn load_raw(filename: &str) -> Result<App> {
         color: Color::Red,
         is_active: true,
         is_visible: true,
+        hotkey: None,
+        last_used: 0,
     }];
 
     Ok(App::new(filename, lines, labels, window))
@@ -79,17 +110,21 @@ fn load_raw(filename: &str) -> Result<App> {
 fn load_spacy(filename: &str) -> Result<App> {
     let window = terminal::window_size()?;
 
-    let (text, ents, labels) = read_spacy(filename)?;
-    let bare_lines = text
+    let (spacy, labels) = read_spacy(filename)?;
+    let bare_lines = spacy
+        .text
         .trim_end()
         .split("\n")
         .map(|x| x.to_owned())
         .enumerate()
         .fold((Vec::new(), 0, window.columns as usize - 2), virtualize_line)
         .0;
-    let lines = assign_labels(bare_lines, &ents, &labels);
+    let lines = assign_labels(bare_lines, &spacy.ents, &labels);
 
-    Ok(App::new(filename, lines, labels, window))
+    let mut app = App::new(filename, lines, labels, window);
+    app.sentences = spacy.sents.into_iter().map(|x| (x.start, x.end)).collect();
+
+    Ok(app)
 }
 
 fn load_tan(filename: &str) -> Result<App> {
@@ -97,29 +132,59 @@ fn load_tan(filename: &str) -> Result<App> {
     serde_json::from_str(&s).map_err(anyhow::Error::from)
 }
 
-fn read_spacy(filename: &str) -> Result<(String, Vec<Ent>, Vec<Label>)> {
+fn read_spacy(filename: &str) -> Result<(Spacy, Vec<Label>)> {
     let f = File::open(filename)?;
     let mut b = BufReader::new(f);
 
-    let spacy: Spacy = serde_json::from_reader(&mut b)?;
+    let mut spacy: Spacy = serde_json::from_reader(&mut b)?;
+    convert_offsets(&mut spacy);
     let labels = parse_labels(&spacy.ents);
 
-    Ok((spacy.text, spacy.ents, labels))
+    Ok((spacy, labels))
+}
+
+/// Turns Spacy character offsets of ents and sents into the byte offsets used for columns.
+fn convert_offsets(spacy: &mut Spacy) {
+    let offsets = spacy
+        .text
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(spacy.text.len()))
+        .collect::<Vec<usize>>();
+    let convert = |x: usize| offsets.get(x).copied().unwrap_or(spacy.text.len());
+
+    for ent in &mut spacy.ents {
+        (ent.start, ent.end) = (convert(ent.start), convert(ent.end));
+    }
+
+    for sent in &mut spacy.sents {
+        (sent.start, sent.end) = (convert(sent.start), convert(sent.end));
+    }
 }
 
 fn virtualize_line(acc: Accumulator, item: Enumerate) -> Accumulator {
     let (mut lines, mut absolute_offset, window_width) = acc;
     let (absolute_row, text) = item;
 
-    let mut virtual_offset = 0;
+    let mut virtual_offset: usize = 0;
 
     loop {
-        let chunk = text[virtual_offset..std::cmp::min(virtual_offset + window_width, text.len())].to_string();
+        let mut end = std::cmp::min(virtual_offset.saturating_add(window_width), text.len());
 
-        let virtual_text = if virtual_offset + chunk.len() < text.len() {
-            chunk.trim_end_matches(|x| !char::is_whitespace(x))
-        } else {
-            &chunk
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        if end == virtual_offset {
+            end += text[virtual_offset..].chars().next().map_or(0, char::len_utf8);
+        }
+
+        let chunk = &text[virtual_offset..end];
+
+        let virtual_text = match chunk.trim_end_matches(|x| !char::is_whitespace(x)) {
+            _ if end == text.len() => chunk,
+            "" => chunk,
+            trimmed => trimmed,
         };
 
         let line = Line {
@@ -147,23 +212,39 @@ fn virtualize_line(acc: Accumulator, item: Enumerate) -> Accumulator {
     (lines, absolute_offset, window_width)
 }
 
-fn assign_labels(mut lines: Vec<Line>, ents: &[Ent], labels: &[Label]) -> Vec<Line> {
-    for ent in ents {
-        let label = labels
-            .iter()
-            .position(|x| x.name == ent.label)
-            .expect(&format!("Cannot find label named {}", ent.label));
+fn assign_labels(lines: Vec<Line>, ents: &[Ent], labels: &[Label]) -> Vec<Line> {
+    let spans = ents
+        .iter()
+        .map(|ent| {
+            let label = labels
+                .iter()
+                .position(|x| x.name == ent.label)
+                .unwrap_or_else(|| panic!("Cannot find label named {}", ent.label));
+
+            Span {
+                start: ent.start,
+                end: ent.end,
+                label,
+                is_reviewed: false,
+            }
+        })
+        .collect::<Vec<Span>>();
+
+    assign_spans(lines, &spans)
+}
 
+fn assign_spans(mut lines: Vec<Line>, spans: &[Span]) -> Vec<Line> {
+    for span in spans {
         let intervals = lines
             .iter()
             .enumerate()
             .filter(|(_, x)| {
-                let tag = (ent.start, ent.end);
+                let tag = (span.start, span.end);
                 let line = (x.absolute_offset, x.absolute_offset + x.width);
                 has_interval_overlap(tag, line)
             })
             .map(|(i, x)| {
-                let tag = (ent.start, ent.end);
+                let tag = (span.start, span.end);
                 let line = (x.absolute_offset, x.width);
 
                 let bounds = calculate_tag_bounds(tag, line);
@@ -175,9 +256,10 @@ fn assign_labels(mut lines: Vec<Line>, ents: &[Ent], labels: &[Label]) -> Vec<Li
             let tag = Tag {
                 start: *start,
                 end: *end,
-                label,
+                label: span.label,
                 has_line_prev: i != 0,
                 has_line_next: i != intervals.len() - 1,
+                is_reviewed: span.is_reviewed,
             };
             lines[*n].tags.push(tag);
         }
@@ -190,7 +272,7 @@ fn has_interval_overlap(tag: Pair, line: Pair) -> bool {
     let (tag_start, tag_end) = tag;
     let (line_start, line_end) = line;
 
-    (tag_end > line_start) ^ (tag_start > line_end)
+    tag_end > line_start && tag_start < line_end
 }
 
 fn calculate_tag_bounds(tag: Pair, line: Pair) -> Pair {
@@ -229,6 +311,7 @@ l        ---
      s1 e1 s2 e2
 */
 #[test]
+#[allow(clippy::bool_comparison)]
 fn test_has_interval_overlap() {
     assert!(has_interval_overlap((11, 13), (10, 14)) == true);
     assert!(has_interval_overlap((10, 14), (11, 13)) == true);
@@ -238,6 +321,27 @@ fn test_has_interval_overlap() {
     assert!(has_interval_overlap((10, 12), (13, 15)) == false);
 }
 
+#[test]
+fn test_has_interval_overlap_touching() {
+    assert!(!has_interval_overlap((12, 15), (10, 12)));
+    assert!(!has_interval_overlap((10, 12), (12, 15)));
+    assert!(has_interval_overlap((11, 15), (10, 12)));
+}
+
+#[test]
+fn test_virtualize_line() {
+    let item = (0, "a verylongtoken b".to_owned());
+    let (lines, absolute_offset, _) = virtualize_line((Vec::new(), 0, 6), item);
+    let texts = lines.iter().map(|x| x.text.as_str()).collect::<Vec<&str>>();
+
+    assert!(texts == ["a ", "verylo", "ngtoke", "n b"]);
+    assert!(absolute_offset == 18);
+    assert!(lines.iter().skip(1).all(|x| x.is_virtual));
+
+    let (lines, _, _) = virtualize_line((Vec::new(), 0, 80), (0, String::new()));
+    assert!(lines.len() == 1 && lines[0].width == 0);
+}
+
 #[test]
 fn test_calculate_tag_bounds() {
     assert!(calculate_tag_bounds((100, 120), (80, 80)) == (20, 40));
@@ -246,6 +350,47 @@ fn test_calculate_tag_bounds() {
     assert!(calculate_tag_bounds((100, 180), (80, 80)) == (20, 80));
 }
 
+fn split_sentences(text: &str) -> Vec<Pair> {
+    let mut sentences = Vec::new();
+    let mut start = None;
+    let mut end = 0;
+    let mut is_terminal = false;
+
+    for (i, c) in text.char_indices() {
+        if !c.is_whitespace() {
+            start.get_or_insert(i);
+            end = i + c.len_utf8();
+            is_terminal = matches!(c, '.' | '!' | '?') || (is_terminal && ")]\"'”’»".contains(c));
+            continue;
+        }
+
+        let is_paragraph = c == '\n' && text[end..i].contains('\n');
+        let is_sentence = is_terminal && text[i..].trim_start().chars().next().is_some_and(|x| !x.is_lowercase());
+
+        if let Some(s) = start.filter(|_| is_paragraph || is_sentence) {
+            sentences.push((s, end));
+            start = None;
+        }
+
+        is_terminal = false;
+    }
+
+    if let Some(s) = start {
+        sentences.push((s, end));
+    }
+
+    sentences
+}
+
+#[test]
+fn test_split_sentences() {
+    let text = "One two. Three (four.) five\n\nSix? e.g. seven\n";
+
+    assert!(split_sentences(text) == vec![(0, 8), (9, 27), (29, 44)]);
+    assert!(split_sentences("Wait!\" He left.") == vec![(0, 6), (7, 15)]);
+    assert!(split_sentences("  \n").is_empty());
+}
+
 fn parse_labels(ents: &Vec<Ent>) -> Vec<Label> {
     let mut labels = Vec::new();
     let mut colors = COLORS.iter().cycle();
@@ -257,6 +402,8 @@ fn parse_labels(ents: &Vec<Ent>) -> Vec<Label> {
                 color: *colors.next().expect("Error looping over colors"),
                 is_active: labels.is_empty(),
                 is_visible: true,
+                hotkey: None,
+                last_used: 0,
             };
 
             labels.push(label);
@@ -266,14 +413,77 @@ fn parse_labels(ents: &Vec<Ent>) -> Vec<Label> {
     labels
 }
 
-pub fn save_tan(app: &mut App) -> Result<()> {
+pub fn rewrap(app: &mut App) {
+    let position = app.get_absolute_position(app.cursor_row + app.offset_row, app.cursor_column);
+    let spans = app.get_spans();
+
+    let lines = app
+        .lines
+        .iter()
+        .chunk_by(|x| x.absolute_row)
+        .into_iter()
+        .map(|(_, x)| x.map(|y| y.text.as_str()).collect::<String>())
+        .enumerate()
+        .fold((Vec::new(), 0, app.get_text_width()), virtualize_line)
+        .0;
+
+    app.lines = assign_spans(lines, &spans);
+    app.nlines = app.lines.len();
+    app.visual.clear();
+
+    let (row, column) = app.find_absolute_position(position);
+    common::move_to_center(app, row, column);
+
+    app.offset_column = 0;
+    app.change |= 0b1111;
+}
+
+#[cfg(test)]
+pub fn load_test_app(text: &str, ents: &[(usize, usize, &str)]) -> App {
+    let window = terminal::WindowSize {
+        rows: 24,
+        columns: 80,
+        width: 0,
+        height: 0,
+    };
+
+    let ents = ents
+        .iter()
+        .map(|&(start, end, label)| Ent {
+            start,
+            end,
+            label: label.to_owned(),
+        })
+        .collect::<Vec<Ent>>();
+    let labels = parse_labels(&ents);
+    let bare_lines = text
+        .split('\n')
+        .map(|x| x.to_owned())
+        .enumerate()
+        .fold((Vec::new(), 0, window.columns as usize - 2), virtualize_line)
+        .0;
+    let lines = assign_labels(bare_lines, &ents, &labels);
+
+    let mut app = App::new("test.tan", lines, labels, window);
+    app.sentences = split_sentences(&app.get_text());
+
+    app
+}
+
+pub fn config_path(name: &str) -> PathBuf {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|x| PathBuf::from(x).join(".config")))
+        .unwrap_or_default();
+
+    config.join("tan").join(name)
+}
+
+pub fn save_tan(app: &mut App, filename: &str) -> Result<()> {
     let mode = app.mode.clone();
     app.set_normal_mode();
 
-    if !app.filename.ends_with(".tan") {
-        app.filename.push_str(".tan")
-    };
-    let mut f = File::create(&app.filename)?;
+    let mut f = File::create(filename)?;
 
     let s = serde_json::to_string(&app)?;
     f.write_all(s.as_bytes())?;
@@ -282,8 +492,38 @@ pub fn save_tan(app: &mut App) -> Result<()> {
     Ok(())
 }
 
+pub fn get_tan_name(filename: &str) -> String {
+    if filename.ends_with(".tan") {
+        filename.to_owned()
+    } else {
+        format!("{}.tan", filename)
+    }
+}
+
 pub fn dump_debug(app: &App) -> Result<()> {
     let mut f = File::create("/tmp/dbg.json")?;
     f.write_all(serde_json::to_string(app)?.as_bytes())
         .map_err(anyhow::Error::from)
 }
+
+#[test]
+fn test_label_recency_saved() {
+    let mut app = load_test_app("alpha beta", &[(0, 5, "PER"), (6, 10, "ORG")]);
+    "viwt".chars().for_each(|x| app.dispatch(x));
+
+    let other: App = serde_json::from_str(&serde_json::to_string(&app).unwrap()).unwrap();
+    assert!(other.tag_count == 1 && other.labels[0].last_used == 1 && other.labels[1].last_used == 0);
+}
+
+#[test]
+fn test_convert_offsets() {
+    let s = r#"{"text": "éé. Next €5", "ents": [{"start": 9, "end": 11, "label": "MONEY"}],
+        "sents": [{"start": 0, "end": 3}, {"start": 4, "end": 11}]}"#;
+    let mut spacy: Spacy = serde_json::from_str(s).unwrap();
+    convert_offsets(&mut spacy);
+
+    assert!((spacy.ents[0].start, spacy.ents[0].end) == (11, 15));
+    assert!((spacy.sents[0].start, spacy.sents[0].end) == (0, 5));
+    assert!((spacy.sents[1].start, spacy.sents[1].end) == (6, 15));
+    assert!(&spacy.text[11..15] == "€5" && &spacy.text[0..5] == "éé.");
+}
@@ -0,0 +1,755 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Result;
+use serde::Deserialize;
+
+use crate::app::{App, Mode};
+
+pub const TIMEOUT: Duration = Duration::from_millis(1000);
+pub const ARGUMENT: char = '\u{e000}';
+
+pub type Action = fn(&mut App);
+
+pub const ACTIONS: &[(&str, Action)] = &[
+    ("command_backspace", App::command_backspace),
+    ("command_delete_line", App::command_delete_line),
+    ("command_delete_word", App::command_delete_word),
+    ("command_down", App::command_down),
+    ("command_esc", App::command_esc),
+    ("command_left", App::command_left),
+    ("command_return", App::command_return),
+    ("command_right", App::command_right),
+    ("command_tab", App::command_tab),
+    ("command_up", App::command_up),
+    ("common_colon", App::common_colon),
+    ("common_t", App::common_t),
+    ("common_zb", App::common_zb),
+    ("common_zt", App::common_zt),
+    ("common_zz", App::common_zz),
+    ("filter_backspace", App::filter_backspace),
+    ("filter_delete_line", App::filter_delete_line),
+    ("filter_delete_word", App::filter_delete_word),
+    ("filter_down", App::filter_down),
+    ("filter_esc", App::filter_esc),
+    ("filter_left", App::filter_left),
+    ("filter_return", App::filter_return),
+    ("filter_right", App::filter_right),
+    ("filter_up", App::filter_up),
+    ("help_G", App::help_G),
+    ("help_N", App::help_N),
+    ("help_g", App::help_g),
+    ("help_j", App::help_j),
+    ("help_k", App::help_k),
+    ("help_n", App::help_n),
+    ("help_pg_down", App::help_pg_down),
+    ("help_pg_up", App::help_pg_up),
+    ("help_q", App::help_q),
+    ("help_slash", App::help_slash),
+    ("modal_B", App::modal_B),
+    ("modal_J", App::modal_J),
+    ("modal_K", App::modal_K),
+    ("modal_a", App::modal_a),
+    ("modal_b", App::modal_b),
+    ("modal_d", App::modal_d),
+    ("modal_h", App::modal_h),
+    ("modal_i", App::modal_i),
+    ("modal_j", App::modal_j),
+    ("modal_k", App::modal_k),
+    ("modal_l", App::modal_l),
+    ("modal_m", App::modal_m),
+    ("modal_return", App::modal_return),
+    ("modal_slash", App::modal_slash),
+    ("modal_v", App::modal_v),
+    ("modal_V", App::modal_V),
+    ("name_backspace", App::name_backspace),
+    ("name_delete_line", App::name_delete_line),
+    ("name_delete_word", App::name_delete_word),
+    ("name_down", App::name_down),
+    ("name_esc", App::name_esc),
+    ("name_left", App::name_left),
+    ("name_right", App::name_right),
+    ("name_tab", App::name_tab),
+    ("name_up", App::name_up),
+    ("normal_end_WORD", App::normal_end_WORD),
+    ("normal_G", App::normal_G),
+    ("normal_H", App::normal_H),
+    ("normal_L", App::normal_L),
+    ("normal_M", App::normal_M),
+    ("normal_N", App::normal_N),
+    ("normal_S", App::normal_S),
+    ("normal_T", App::normal_T),
+    ("normal_V", App::normal_V),
+    ("normal_active_next", App::normal_active_next),
+    ("normal_active_prev", App::normal_active_prev),
+    ("normal_B", App::normal_B),
+    ("normal_W", App::normal_W),
+    ("normal_b", App::normal_b),
+    ("normal_dollar", App::normal_dollar),
+    ("normal_F", App::normal_F),
+    ("normal_comma", App::normal_comma),
+    ("normal_e", App::normal_e),
+    ("normal_f", App::normal_f),
+    ("normal_gT", App::normal_gT),
+    ("normal_gt", App::normal_gt),
+    ("normal_gE", App::normal_gE),
+    ("normal_ge", App::normal_ge),
+    ("normal_as", App::normal_as),
+    ("normal_aw", App::normal_aw),
+    ("normal_h", App::normal_h),
+    ("normal_ip", App::normal_ip),
+    ("normal_jump_newer", App::normal_jump_newer),
+    ("normal_jump_older", App::normal_jump_older),
+    ("normal_iquote", App::normal_iquote),
+    ("normal_is", App::normal_is),
+    ("normal_iw", App::normal_iw),
+    ("normal_j", App::normal_j),
+    ("normal_k", App::normal_k),
+    ("normal_l", App::normal_l),
+    ("normal_mark", App::normal_mark),
+    ("normal_n", App::normal_n),
+    ("normal_overlap_next", App::normal_overlap_next),
+    ("normal_overlap_prev", App::normal_overlap_prev),
+    ("normal_pg_down", App::normal_pg_down),
+    ("normal_pg_up", App::normal_pg_up),
+    ("normal_question", App::normal_question),
+    ("normal_quote", App::normal_quote),
+    ("normal_r", App::normal_r),
+    ("normal_s", App::normal_s),
+    ("normal_scroll_down", App::normal_scroll_down),
+    ("normal_scroll_up", App::normal_scroll_up),
+    ("normal_semicolon", App::normal_semicolon),
+    ("normal_t", App::normal_t),
+    ("normal_u", App::normal_u),
+    ("normal_v", App::normal_v),
+    ("normal_w", App::normal_w),
+    ("picker_backspace", App::picker_backspace),
+    ("picker_delete_line", App::picker_delete_line),
+    ("picker_delete_word", App::picker_delete_word),
+    ("picker_down", App::picker_down),
+    ("picker_esc", App::picker_esc),
+    ("picker_left", App::picker_left),
+    ("picker_return", App::picker_return),
+    ("picker_right", App::picker_right),
+    ("picker_up", App::picker_up),
+    ("review_a", App::review_a),
+    ("review_e", App::review_e),
+    ("review_esc", App::review_esc),
+    ("review_j", App::review_j),
+    ("review_k", App::review_k),
+    ("review_r", App::review_r),
+    ("review_x", App::review_x),
+    ("visual_B", App::visual_B),
+    ("visual_end_WORD", App::visual_end_WORD),
+    ("visual_V", App::visual_V),
+    ("visual_T", App::visual_T),
+    ("visual_W", App::visual_W),
+    ("visual_as", App::visual_as),
+    ("visual_aw", App::visual_aw),
+    ("visual_b", App::visual_b),
+    ("visual_backslash", App::visual_backslash),
+    ("visual_dollar", App::visual_dollar),
+    ("visual_F", App::visual_F),
+    ("visual_comma", App::visual_comma),
+    ("visual_e", App::visual_e),
+    ("visual_f", App::visual_f),
+    ("visual_gT", App::visual_gT),
+    ("visual_gt", App::visual_gt),
+    ("visual_gE", App::visual_gE),
+    ("visual_ge", App::visual_ge),
+    ("visual_h", App::visual_h),
+    ("visual_ip", App::visual_ip),
+    ("visual_pick_label", App::visual_pick_label),
+    ("visual_iquote", App::visual_iquote),
+    ("visual_is", App::visual_is),
+    ("visual_iw", App::visual_iw),
+    ("visual_j", App::visual_j),
+    ("visual_k", App::visual_k),
+    ("visual_l", App::visual_l),
+    ("visual_o", App::visual_o),
+    ("visual_s", App::visual_s),
+    ("visual_scroll_down", App::visual_scroll_down),
+    ("visual_scroll_up", App::visual_scroll_up),
+    ("visual_semicolon", App::visual_semicolon),
+    ("visual_v", App::visual_v),
+    ("visual_w", App::visual_w),
+];
+
+const BINDINGS: &[(Mode, &str, &str)] = &[
+    (Mode::Command, "<BS>", "command_backspace"),
+    (Mode::Command, "<CR>", "command_return"),
+    (Mode::Command, "<Esc>", "command_esc"),
+    (Mode::Command, "<Left>", "command_left"),
+    (Mode::Command, "<Right>", "command_right"),
+    (Mode::Command, "<Up>", "command_up"),
+    (Mode::Command, "<Down>", "command_down"),
+    (Mode::Command, "<C-u>", "command_delete_line"),
+    (Mode::Command, "<C-w>", "command_delete_word"),
+    (Mode::Command, "<Tab>", "command_tab"),
+    (Mode::Filter, "<BS>", "filter_backspace"),
+    (Mode::Filter, "<CR>", "filter_return"),
+    (Mode::Filter, "<Esc>", "filter_esc"),
+    (Mode::Filter, "<Left>", "filter_left"),
+    (Mode::Filter, "<Right>", "filter_right"),
+    (Mode::Filter, "<Down>", "filter_down"),
+    (Mode::Filter, "<Up>", "filter_up"),
+    (Mode::Filter, "<C-n>", "filter_down"),
+    (Mode::Filter, "<C-p>", "filter_up"),
+    (Mode::Filter, "<C-u>", "filter_delete_line"),
+    (Mode::Filter, "<C-w>", "filter_delete_word"),
+    (Mode::Help, "j", "help_j"),
+    (Mode::Help, "k", "help_k"),
+    (Mode::Help, "<Down>", "help_j"),
+    (Mode::Help, "<Up>", "help_k"),
+    (Mode::Help, "<C-n>", "help_pg_down"),
+    (Mode::Help, "<C-p>", "help_pg_up"),
+    (Mode::Help, "gg", "help_g"),
+    (Mode::Help, "G", "help_G"),
+    (Mode::Help, "/", "help_slash"),
+    (Mode::Help, "n", "help_n"),
+    (Mode::Help, "N", "help_N"),
+    (Mode::Help, "q", "help_q"),
+    (Mode::Help, "?", "help_q"),
+    (Mode::Help, "<Esc>", "help_q"),
+    (Mode::Modal, "T", "modal_m"),
+    (Mode::Modal, "m", "modal_m"),
+    (Mode::Modal, "<Esc>", "modal_m"),
+    (Mode::Modal, "i", "modal_i"),
+    (Mode::Modal, "h", "modal_h"),
+    (Mode::Modal, "j", "modal_j"),
+    (Mode::Modal, "k", "modal_k"),
+    (Mode::Modal, "l", "modal_l"),
+    (Mode::Modal, "v", "modal_v"),
+    (Mode::Modal, "V", "modal_V"),
+    (Mode::Modal, "a", "modal_a"),
+    (Mode::Modal, "d", "modal_d"),
+    (Mode::Modal, "<CR>", "modal_return"),
+    (Mode::Modal, "/", "modal_slash"),
+    (Mode::Modal, "J", "modal_J"),
+    (Mode::Modal, "K", "modal_K"),
+    (Mode::Modal, "b<Char>", "modal_b"),
+    (Mode::Modal, "B", "modal_B"),
+    (Mode::Name, "<BS>", "name_backspace"),
+    (Mode::Name, "<CR>", "name_esc"),
+    (Mode::Name, "<Esc>", "name_esc"),
+    (Mode::Name, "<Left>", "name_left"),
+    (Mode::Name, "<Right>", "name_right"),
+    (Mode::Name, "<Up>", "name_up"),
+    (Mode::Name, "<Down>", "name_down"),
+    (Mode::Name, "<C-u>", "name_delete_line"),
+    (Mode::Name, "<C-w>", "name_delete_word"),
+    (Mode::Name, "<Tab>", "name_tab"),
+    (Mode::Normal, ":", "common_colon"),
+    (Mode::Normal, "T", "normal_T"),
+    (Mode::Normal, "?", "normal_question"),
+    (Mode::Normal, "m<Char>", "normal_mark"),
+    (Mode::Normal, "'<Char>", "normal_quote"),
+    (Mode::Normal, "<C-o>", "normal_jump_older"),
+    (Mode::Normal, "<C-i>", "normal_jump_newer"),
+    (Mode::Normal, "v", "normal_v"),
+    (Mode::Normal, "V", "normal_V"),
+    (Mode::Normal, "h", "normal_h"),
+    (Mode::Normal, "j", "normal_j"),
+    (Mode::Normal, "k", "normal_k"),
+    (Mode::Normal, "l", "normal_l"),
+    (Mode::Normal, "H", "normal_H"),
+    (Mode::Normal, "M", "normal_M"),
+    (Mode::Normal, "L", "normal_L"),
+    (Mode::Normal, "<C-n>", "normal_pg_down"),
+    (Mode::Normal, "<C-p>", "normal_pg_up"),
+    (Mode::Normal, "<C-e>", "normal_scroll_down"),
+    (Mode::Normal, "<C-y>", "normal_scroll_up"),
+    (Mode::Normal, "zz", "common_zz"),
+    (Mode::Normal, "zt", "common_zt"),
+    (Mode::Normal, "zb", "common_zb"),
+    (Mode::Normal, "s", "normal_s"),
+    (Mode::Normal, "0", "normal_s"),
+    (Mode::Normal, "$", "normal_dollar"),
+    (Mode::Normal, "S", "normal_S"),
+    (Mode::Normal, "gg", "normal_S"),
+    (Mode::Normal, "G", "normal_G"),
+    (Mode::Normal, "w", "normal_w"),
+    (Mode::Normal, "W", "normal_W"),
+    (Mode::Normal, "e", "normal_e"),
+    (Mode::Normal, "E", "normal_end_WORD"),
+    (Mode::Normal, "ge", "normal_ge"),
+    (Mode::Normal, "gE", "normal_gE"),
+    (Mode::Normal, "b", "normal_b"),
+    (Mode::Normal, "B", "normal_B"),
+    (Mode::Normal, "f<Char>", "normal_f"),
+    (Mode::Normal, "F<Char>", "normal_F"),
+    (Mode::Normal, "gt<Char>", "normal_gt"),
+    (Mode::Normal, "gT<Char>", "normal_gT"),
+    (Mode::Normal, ";", "normal_semicolon"),
+    (Mode::Normal, ",", "normal_comma"),
+    (Mode::Normal, "n", "normal_n"),
+    (Mode::Normal, "N", "normal_N"),
+    (Mode::Normal, "]t", "normal_n"),
+    (Mode::Normal, "[t", "normal_N"),
+    (Mode::Normal, "]a", "normal_active_next"),
+    (Mode::Normal, "[a", "normal_active_prev"),
+    (Mode::Normal, "]o", "normal_overlap_next"),
+    (Mode::Normal, "[o", "normal_overlap_prev"),
+    (Mode::Normal, "r", "normal_r"),
+    (Mode::Normal, "t", "normal_t"),
+    (Mode::Normal, "u", "normal_u"),
+    (Mode::Normal, "iw", "normal_iw"),
+    (Mode::Normal, "aw", "normal_aw"),
+    (Mode::Normal, "is", "normal_is"),
+    (Mode::Normal, "as", "normal_as"),
+    (Mode::Normal, "ip", "normal_ip"),
+    (Mode::Normal, "i\"", "normal_iquote"),
+    (Mode::Picker, "<BS>", "picker_backspace"),
+    (Mode::Picker, "<CR>", "picker_return"),
+    (Mode::Picker, "<Esc>", "picker_esc"),
+    (Mode::Picker, "<Left>", "picker_left"),
+    (Mode::Picker, "<Right>", "picker_right"),
+    (Mode::Picker, "<Down>", "picker_down"),
+    (Mode::Picker, "<Up>", "picker_up"),
+    (Mode::Picker, "<C-n>", "picker_down"),
+    (Mode::Picker, "<C-p>", "picker_up"),
+    (Mode::Picker, "<C-u>", "picker_delete_line"),
+    (Mode::Picker, "<C-w>", "picker_delete_word"),
+    (Mode::Review, "j", "review_j"),
+    (Mode::Review, "k", "review_k"),
+    (Mode::Review, "a", "review_a"),
+    (Mode::Review, "e", "review_e"),
+    (Mode::Review, "r", "review_r"),
+    (Mode::Review, "x", "review_x"),
+    (Mode::Review, "<Esc>", "review_esc"),
+    (Mode::Visual, ":", "common_colon"),
+    (Mode::Visual, "T", "visual_T"),
+    (Mode::Visual, "v", "visual_v"),
+    (Mode::Visual, "V", "visual_V"),
+    (Mode::Visual, "o", "visual_o"),
+    (Mode::Visual, "h", "visual_h"),
+    (Mode::Visual, "j", "visual_j"),
+    (Mode::Visual, "k", "visual_k"),
+    (Mode::Visual, "l", "visual_l"),
+    (Mode::Visual, "s", "visual_s"),
+    (Mode::Visual, "0", "visual_s"),
+    (Mode::Visual, "$", "visual_dollar"),
+    (Mode::Visual, "<C-e>", "visual_scroll_down"),
+    (Mode::Visual, "<C-y>", "visual_scroll_up"),
+    (Mode::Visual, "zz", "common_zz"),
+    (Mode::Visual, "zt", "common_zt"),
+    (Mode::Visual, "zb", "common_zb"),
+    (Mode::Visual, "w", "visual_w"),
+    (Mode::Visual, "W", "visual_W"),
+    (Mode::Visual, "e", "visual_e"),
+    (Mode::Visual, "E", "visual_end_WORD"),
+    (Mode::Visual, "ge", "visual_ge"),
+    (Mode::Visual, "gE", "visual_gE"),
+    (Mode::Visual, "b", "visual_b"),
+    (Mode::Visual, "B", "visual_B"),
+    (Mode::Visual, "f<Char>", "visual_f"),
+    (Mode::Visual, "F<Char>", "visual_F"),
+    (Mode::Visual, "gt<Char>", "visual_gt"),
+    (Mode::Visual, "gT<Char>", "visual_gT"),
+    (Mode::Visual, ";", "visual_semicolon"),
+    (Mode::Visual, ",", "visual_comma"),
+    (Mode::Visual, "iw", "visual_iw"),
+    (Mode::Visual, "aw", "visual_aw"),
+    (Mode::Visual, "is", "visual_is"),
+    (Mode::Visual, "as", "visual_as"),
+    (Mode::Visual, "ip", "visual_ip"),
+    (Mode::Visual, "i\"", "visual_iquote"),
+    (Mode::Visual, "t", "common_t"),
+    (Mode::Visual, "<C-t>", "visual_pick_label"),
+    (Mode::Visual, "\\<Char>", "visual_backslash"),
+];
+
+/// Actions renamed since keys.toml was introduced, so existing files keep loading.
+const DEPRECATED_ACTIONS: &[(&str, &str)] = &[
+    ("normal_E", "normal_G"),
+    ("normal_m", "normal_T"),
+    ("visual_m", "visual_T"),
+];
+
+const KEY_NAMES: &[(&str, char)] = &[
+    ("BS", '\x08'),
+    ("Tab", '\x09'),
+    ("CR", '\x0a'),
+    ("Enter", '\x0a'),
+    ("Esc", '\x1b'),
+    ("Space", ' '),
+    ("lt", '<'),
+    ("Char", ARGUMENT),
+    ("Left", '\u{e001}'),
+    ("Right", '\u{e002}'),
+    ("Up", '\u{e003}'),
+    ("Down", '\u{e004}'),
+];
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeysFile {
+    #[serde(default)]
+    command: BTreeMap<String, String>,
+    #[serde(default)]
+    filter: BTreeMap<String, String>,
+    #[serde(default)]
+    help: BTreeMap<String, String>,
+    #[serde(default)]
+    modal: BTreeMap<String, String>,
+    #[serde(default)]
+    name: BTreeMap<String, String>,
+    #[serde(default)]
+    normal: BTreeMap<String, String>,
+    #[serde(default)]
+    picker: BTreeMap<String, String>,
+    #[serde(default)]
+    review: BTreeMap<String, String>,
+    #[serde(default)]
+    visual: BTreeMap<String, String>,
+}
+
+#[derive(Debug)]
+pub struct Binding {
+    pub mode: Mode,
+    pub keys: String,
+    pub action: &'static str,
+}
+
+#[derive(Debug)]
+pub struct Keymap {
+    pub bindings: Vec<Binding>,
+}
+
+pub enum Motion {
+    Exclusive,
+    Inclusive,
+    Linewise,
+}
+
+enum Lookup {
+    Exact(&'static str),
+    Prefix(Option<&'static str>),
+    None,
+}
+
+impl Default for Keymap {
+    fn default() -> Keymap {
+        let bindings = BINDINGS
+            .iter()
+            .map(|(mode, keys, action)| Binding {
+                mode: mode.clone(),
+                keys: parse_keys(keys).expect("Error parsing default keybindings"),
+                action,
+            })
+            .collect();
+
+        Keymap { bindings }
+    }
+}
+
+impl Keymap {
+    pub fn load(path: &Path) -> Result<Keymap> {
+        if path.exists() {
+            Keymap::parse(&std::fs::read_to_string(path)?)
+        } else {
+            Ok(Keymap::default())
+        }
+    }
+
+    fn parse(s: &str) -> Result<Keymap> {
+        let mut keymap = Keymap::default();
+        let keys: KeysFile = toml::from_str(s)?;
+
+        let modes = [
+            (Mode::Command, keys.command),
+            (Mode::Filter, keys.filter),
+            (Mode::Help, keys.help),
+            (Mode::Modal, keys.modal),
+            (Mode::Name, keys.name),
+            (Mode::Normal, keys.normal),
+            (Mode::Picker, keys.picker),
+            (Mode::Review, keys.review),
+            (Mode::Visual, keys.visual),
+        ];
+
+        for (mode, bindings) in modes {
+            for (keys, action) in bindings {
+                keymap.bind(mode.clone(), &parse_keys(&keys)?, &action)?;
+            }
+        }
+
+        Ok(keymap)
+    }
+
+    fn bind(&mut self, mode: Mode, keys: &str, action: &str) -> Result<()> {
+        self.bindings.retain(|x| !(x.mode == mode && x.keys == keys));
+
+        if !action.is_empty() {
+            let action = DEPRECATED_ACTIONS
+                .iter()
+                .find(|(x, _)| *x == action)
+                .map_or(action, |(_, x)| x);
+            let (action, _) = ACTIONS
+                .iter()
+                .find(|(x, _)| *x == action)
+                .ok_or_else(|| anyhow::Error::msg(format!("Unknown action {}", action)))?;
+
+            self.bindings.push(Binding {
+                mode,
+                keys: keys.to_owned(),
+                action,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn lookup(&self, mode: &Mode, keys: &str) -> Lookup {
+        let bindings = self
+            .bindings
+            .iter()
+            .filter(|x| &x.mode == mode && is_prefix(&x.keys, keys))
+            .collect::<Vec<&Binding>>();
+
+        let exact = bindings
+            .iter()
+            .find(|x| x.keys.chars().count() == keys.chars().count())
+            .map(|x| x.action);
+
+        match (bindings.len(), exact) {
+            (0, _) => Lookup::None,
+            (1, Some(action)) => Lookup::Exact(action),
+            (_, action) => Lookup::Prefix(action),
+        }
+    }
+
+    fn is_awaiting_argument(&self, mode: &Mode, keys: &str) -> bool {
+        let n = keys.chars().count();
+
+        self.bindings
+            .iter()
+            .filter(|x| &x.mode == mode && is_prefix(&x.keys, keys))
+            .any(|x| x.keys.chars().nth(n) == Some(ARGUMENT))
+    }
+}
+
+impl App {
+    pub fn dispatch(&mut self, key: char) {
+        if !self.message.is_empty() {
+            self.message.clear();
+            self.change |= 0b0001;
+        }
+
+        if self.pending.is_empty() && self.is_countable_key(key) {
+            self.common_digit(key);
+            return;
+        }
+
+        if !self.pending.is_empty() {
+            self.change |= 0b0001;
+        }
+
+        self.pending.push(key);
+
+        match self.keymap.lookup(&self.mode, &self.pending) {
+            Lookup::Exact(name) => {
+                self.argument = key;
+                self.pending.clear();
+                self.execute(name);
+            }
+            Lookup::Prefix(_) => self.change |= 0b0001,
+            Lookup::None => {
+                if self.pending.chars().count() == 1 {
+                    self.dispatch_char(key);
+                }
+
+                if self.operator.take().is_some() {
+                    self.change |= 0b0001;
+                }
+
+                self.pending.clear();
+                self.count = 0;
+            }
+        }
+    }
+
+    /// Pending keys time out unless a binding is waiting for its `<Char>` argument.
+    pub fn is_pending_timeout(&self) -> bool {
+        !self.pending.is_empty() && !self.keymap.is_awaiting_argument(&self.mode, &self.pending)
+    }
+
+    pub fn dispatch_timeout(&mut self) {
+        let lookup = self.keymap.lookup(&self.mode, &self.pending);
+
+        self.pending.clear();
+        self.change |= 0b0001;
+
+        if let Lookup::Exact(name) | Lookup::Prefix(Some(name)) = lookup {
+            self.execute(name);
+        } else {
+            self.count = 0;
+            self.operator = None;
+        }
+    }
+
+    fn execute(&mut self, name: &'static str) {
+        if let Some(action) = find_action(name) {
+            if self.operator.is_some() {
+                self.apply_operator(name, action);
+            } else {
+                let count = self.count;
+                self.common_repeat(action);
+
+                if self.operator.is_some() {
+                    self.operator_count = count;
+                }
+            }
+        }
+    }
+
+    fn dispatch_char(&mut self, c: char) {
+        match (&self.mode, c) {
+            (Mode::Command, c) if is_text_char(c) => self.command_char(c),
+            (Mode::Filter, c) if is_text_char(c) => self.filter_char(c),
+            (Mode::Picker, c) if is_text_char(c) => self.picker_char(c),
+            (Mode::Name, c) if is_text_char(c) && !c.is_whitespace() => self.name_char(c),
+            _ => (),
+        }
+    }
+
+    fn is_countable_key(&self, key: char) -> bool {
+        matches!(self.mode, Mode::Normal | Mode::Visual)
+            && match key {
+                '1'..='9' => true,
+                '0' => self.count > 0,
+                _ => false,
+            }
+    }
+}
+
+fn is_text_char(c: char) -> bool {
+    !c.is_control() && !('\u{e000}'..='\u{f8ff}').contains(&c)
+}
+
+fn is_prefix(binding: &str, keys: &str) -> bool {
+    binding.chars().count() >= keys.chars().count()
+        && binding
+            .chars()
+            .zip(keys.chars())
+            .all(|(x, y)| x == y || (x == ARGUMENT && y != '\x1b'))
+}
+
+pub fn get_motion(name: &str, is_forward: bool) -> Motion {
+    match name {
+        "normal_G" | "normal_dollar" | "normal_e" | "normal_end_WORD" | "normal_gE" | "normal_ge" => Motion::Inclusive,
+        "normal_F" | "normal_comma" | "normal_f" | "normal_gT" | "normal_gt" | "normal_semicolon" if is_forward => {
+            Motion::Inclusive
+        }
+        "normal_H" | "normal_L" | "normal_M" | "normal_j" | "normal_k" | "normal_pg_down" | "normal_pg_up" => {
+            Motion::Linewise
+        }
+        _ => Motion::Exclusive,
+    }
+}
+
+pub fn find_action(name: &str) -> Option<Action> {
+    ACTIONS.iter().find(|(x, _)| *x == name).map(|(_, action)| *action)
+}
+
+pub fn parse_keys(s: &str) -> Result<String> {
+    let mut keys = String::new();
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '<' {
+            keys.push(c);
+            continue;
+        }
+
+        let name = chars.by_ref().take_while(|x| *x != '>').collect::<String>();
+
+        let key = if let Some(c) = name.strip_prefix("C-").filter(|x| x.len() == 1) {
+            to_control(c.chars().next().expect("Error parsing control key"))
+        } else {
+            KEY_NAMES
+                .iter()
+                .find(|(x, _)| x.eq_ignore_ascii_case(&name))
+                .map(|(_, c)| *c)
+        };
+
+        keys.push(key.ok_or_else(|| anyhow::Error::msg(format!("Unknown key <{}>", name)))?);
+    }
+
+    Ok(keys)
+}
+
+pub fn format_keys(keys: &str) -> String {
+    keys.chars()
+        .map(|c| match KEY_NAMES.iter().find(|(_, x)| *x == c) {
+            Some((name, _)) if c != ' ' => format!("<{}>", name),
+            _ if c.is_ascii_control() => format!("<C-{}>", ((c as u8) | 0x60) as char),
+            _ => c.to_string(),
+        })
+        .collect()
+}
+
+pub fn to_control(c: char) -> Option<char> {
+    c.is_ascii_alphabetic()
+        .then(|| ((c.to_ascii_lowercase() as u8) & 0x1f) as char)
+}
+
+#[test]
+fn test_parse_keys() {
+    assert!(parse_keys("gg").unwrap() == "gg");
+    assert!(parse_keys("<C-n>").unwrap() == "\x0e");
+    assert!(parse_keys("]<lt><CR>").unwrap() == "]<\x0a");
+    assert!(parse_keys("<Nope>").is_err());
+}
+
+#[test]
+fn test_parse_keymap() {
+    let keymap = Keymap::parse("[normal]\n\"d\" = \"normal_h\"\n\"h\" = \"\"\n").unwrap();
+    let is_bound = |keys: &str, action: &str| {
+        keymap
+            .bindings
+            .iter()
+            .any(|x| x.mode == Mode::Normal && x.keys == keys && x.action == action)
+    };
+
+    assert!(is_bound("d", "normal_h"));
+    assert!(!is_bound("h", "normal_h"));
+    assert!(is_bound("j", "normal_j"));
+    assert!(Keymap::parse("[normal]\n\"d\" = \"normal_nope\"\n").is_err());
+    assert!(Keymap::parse("[visual]\n\"M\" = \"visual_m\"\n")
+        .unwrap()
+        .bindings
+        .iter()
+        .any(|x| x.mode == Mode::Visual && x.keys == "M" && x.action == "visual_T"));
+
+    let keymap = Keymap::parse("[normal]\n\"M\" = \"normal_m\"\n\"Z\" = \"normal_E\"\n").unwrap();
+    assert!(keymap.bindings.iter().any(|x| x.keys == "M" && x.action == "normal_T"));
+    assert!(keymap.bindings.iter().any(|x| x.keys == "Z" && x.action == "normal_G"));
+    assert!(Keymap::parse("[nope]\n").is_err());
+}
+
+#[test]
+fn test_is_prefix() {
+    assert!(is_prefix("gg", "g"));
+    assert!(is_prefix("f\u{e000}", "f,"));
+    assert!(!is_prefix("f\u{e000}", "f\x1b"));
+    assert!(!is_prefix("g", "gg"));
+}
+
+#[test]
+fn test_is_awaiting_argument() {
+    let keymap = Keymap::default();
+
+    assert!(keymap.is_awaiting_argument(&Mode::Normal, "f"));
+    assert!(keymap.is_awaiting_argument(&Mode::Normal, "gt"));
+    assert!(!keymap.is_awaiting_argument(&Mode::Normal, "g"));
+    assert!(!keymap.is_awaiting_argument(&Mode::Normal, "z"));
+}
+
+#[test]
+fn test_format_keys() {
+    assert!(format_keys("\x0e") == "<C-n>");
+    assert!(format_keys("\x0a") == "<CR>");
+    assert!(format_keys("g g") == "g g");
+}
@@ -9,59 +9,148 @@ impl App {
         self.mode = Mode::Modal;
     }
 
+    pub fn open_modal(&mut self) {
+        self.modal_filter.clear();
+        self.manage_modal_offset();
+        self.set_modal_mode();
+    }
+
+    pub fn get_modal_size(&self) -> (usize, usize) {
+        let width = std::cmp::min(self.settings.modal_width, self.window_width.saturating_sub(2));
+        let height = std::cmp::min(self.settings.modal_height, self.window_height.saturating_sub(3));
+
+        (width, std::cmp::max(height, 2))
+    }
+
+    /// Whether the current row passes the filter, as modal actions only act on a row the user can see.
+    fn is_modal_row_shown(&self) -> bool {
+        self.get_modal_rows().contains(&self.modal_row)
+    }
+
+    pub fn get_modal_rows(&self) -> Vec<usize> {
+        let filter = self.modal_filter.text.to_lowercase();
+
+        self.labels
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| x.name.to_lowercase().contains(&filter))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    pub fn get_modal_line(&self) -> usize {
+        let position = self
+            .get_modal_rows()
+            .iter()
+            .position(|&x| x == self.modal_row)
+            .unwrap_or(0);
+
+        (position + 1).saturating_sub(self.modal_offset)
+    }
+
+    pub fn manage_modal_offset(&mut self) {
+        let rows = self.get_modal_rows();
+        let (_, height) = self.get_modal_size();
+        let visible = height - 1;
+
+        let position = match rows.iter().position(|&x| x == self.modal_row) {
+            Some(position) => position,
+            None => {
+                self.modal_row = rows.first().copied().unwrap_or(self.modal_row);
+                0
+            }
+        };
+
+        if position < self.modal_offset {
+            self.modal_offset = position;
+        } else if position >= self.modal_offset + visible {
+            self.modal_offset = position + 1 - visible;
+        }
+
+        self.modal_offset = std::cmp::min(self.modal_offset, rows.len().saturating_sub(visible));
+    }
+
     pub fn modal_m(&mut self) {
+        self.modal_filter.clear();
+        self.review_tags.clear();
         self.set_normal_mode();
         self.change |= 0b_0000_1011;
     }
 
     pub fn modal_h(&mut self) {
+        if !self.is_modal_row_shown() {
+            return;
+        }
+
         let old = self.get_label_color();
         let new = (old as i8 - 1).rem_euclid(18) as usize;
 
         self.labels[self.modal_row].color = app::COLORS[new];
+        self.is_dirty = true;
         self.change |= 0b_0001_0011;
     }
 
     pub fn modal_j(&mut self) {
-        self.modal_row = (self.modal_row + 1) % self.labels.len();
+        let rows = self.get_modal_rows();
+
+        if let Some(position) = rows.iter().position(|&x| x == self.modal_row) {
+            self.modal_row = rows[(position + 1) % rows.len()];
+        }
+
+        self.manage_modal_offset();
         self.change |= 0b_0001_1000;
     }
 
     pub fn modal_k(&mut self) {
-        if self.modal_row > 0 {
-            self.modal_row = self.modal_row - 1;
-        } else {
-            self.modal_row = self.labels.len() - 1;
+        let rows = self.get_modal_rows();
+
+        if let Some(position) = rows.iter().position(|&x| x == self.modal_row) {
+            self.modal_row = rows[(position + rows.len() - 1) % rows.len()];
         }
+
+        self.manage_modal_offset();
         self.change |= 0b_0001_1000;
     }
 
     pub fn modal_l(&mut self) {
+        if !self.is_modal_row_shown() {
+            return;
+        }
+
         let old = self.get_label_color();
         let new = (old + 1).rem_euclid(18);
 
         self.labels[self.modal_row].color = app::COLORS[new];
+        self.is_dirty = true;
         self.change |= 0b_0001_0011;
     }
 
     pub fn modal_v(&mut self) {
+        if !self.is_modal_row_shown() {
+            return;
+        }
+
         self.labels[self.modal_row].is_visible ^= true;
+        self.is_dirty = true;
         self.change |= 0b_0001_0011;
     }
 
     #[allow(non_snake_case)]
     pub fn modal_V(&mut self) {
         self.labels.iter_mut().for_each(|x| x.is_visible ^= true);
+        self.is_dirty = true;
         self.change |= 0b_0001_0011;
     }
 
     pub fn modal_a(&mut self) {
-        if self.labels.len() < 24 {
+        if self.labels.len() < self.settings.label_limit {
             let label = Label {
-                name: "new_label".to_owned(),
+                name: "new_label".chars().take(self.settings.name_limit).collect(),
                 color: app::COLORS[self.rng],
                 is_active: false,
                 is_visible: true,
+                hotkey: None,
+                last_used: 0,
             };
 
             self.labels.insert(self.modal_row + 1, label);
@@ -73,12 +162,23 @@ impl App {
                 })
             });
 
+            if self.modal_active > self.modal_row {
+                self.modal_active += 1;
+            }
+
             self.rng = (self.rng + 1) % app::COLORS.len();
+            self.modal_filter.clear();
+            self.manage_modal_offset();
+            self.is_dirty = true;
             self.change |= 0b_0001_0011;
         }
     }
 
     pub fn modal_d(&mut self) {
+        if !self.is_modal_row_shown() {
+            return;
+        }
+
         if self.labels.len() > 1 {
             self.lines.iter_mut().for_each(|x| {
                 x.tags = x
@@ -98,6 +198,11 @@ impl App {
             });
 
             self.labels.remove(self.modal_row);
+
+            if self.modal_active > self.modal_row {
+                self.modal_active -= 1;
+            }
+
             self.modal_row = self.modal_row.saturating_sub(1).rem_euclid(self.labels.len());
 
             if !self.labels.iter().any(|x| x.is_active) {
@@ -105,16 +210,100 @@ impl App {
                 self.labels[self.modal_active].is_active = true;
             }
 
+            self.manage_modal_offset();
+            self.is_dirty = true;
             self.change |= 0b_0001_0011;
         }
     }
 
+    #[allow(non_snake_case)]
+    pub fn modal_J(&mut self) {
+        if !self.is_modal_row_shown() {
+            return;
+        }
+
+        if self.modal_row + 1 < self.labels.len() {
+            self.swap_labels(self.modal_row, self.modal_row + 1);
+        }
+    }
+
+    #[allow(non_snake_case)]
+    pub fn modal_K(&mut self) {
+        if !self.is_modal_row_shown() {
+            return;
+        }
+
+        if self.modal_row > 0 {
+            self.swap_labels(self.modal_row, self.modal_row - 1);
+        }
+    }
+
+    fn swap_labels(&mut self, row: usize, other: usize) {
+        let swap = |x: usize| match x {
+            x if x == row => other,
+            x if x == other => row,
+            x => x,
+        };
+
+        self.labels.swap(row, other);
+        self.lines
+            .iter_mut()
+            .for_each(|x| x.tags.iter_mut().for_each(|y| y.label = swap(y.label)));
+
+        self.modal_active = swap(self.modal_active);
+        self.modal_row = other;
+
+        self.manage_modal_offset();
+        self.is_dirty = true;
+        self.change |= 0b_0001_1011;
+    }
+
+    pub fn modal_b(&mut self) {
+        if self.argument.is_whitespace() || !self.is_modal_row_shown() {
+            return;
+        }
+
+        for label in self.labels.iter_mut().filter(|x| x.hotkey == Some(self.argument)) {
+            label.hotkey = None;
+        }
+
+        self.labels[self.modal_row].hotkey = Some(self.argument);
+        self.is_dirty = true;
+        self.change |= 0b_0001_0000;
+    }
+
+    #[allow(non_snake_case)]
+    pub fn modal_B(&mut self) {
+        if !self.is_modal_row_shown() {
+            return;
+        }
+
+        if self.labels[self.modal_row].hotkey.take().is_some() {
+            self.is_dirty = true;
+            self.change |= 0b_0001_0000;
+        }
+    }
+
+    pub fn modal_slash(&mut self) {
+        self.set_filter_mode();
+        self.change |= 0b_0001_1000;
+    }
+
     pub fn modal_i(&mut self) {
+        if !self.is_modal_row_shown() {
+            return;
+        }
+
         self.set_name_mode();
         self.change |= 0b_0000_1000;
     }
 
     pub fn modal_return(&mut self) {
+        if !self.is_modal_row_shown() {
+            return;
+        }
+
+        self.is_dirty |= self.modal_active != self.modal_row;
         self.labels[self.modal_active].is_active = false;
         self.modal_active = self.modal_row;
 
@@ -129,3 +318,29 @@ impl App {
             .expect("Error retrieving label color")
     }
 }
+
+#[test]
+fn test_modal_filter_without_matches() {
+    let mut app = crate::io::load_test_app("alpha beta", &[(0, 5, "PER"), (6, 10, "ORG")]);
+    "T/xyz\n".chars().for_each(|x| app.dispatch(x));
+    assert!(app.is_modal_mode() && app.get_modal_rows().is_empty());
+
+    "dhlvJ\nBbx".chars().for_each(|x| app.dispatch(x));
+    assert!(app.labels.len() == 2 && !app.is_dirty);
+    assert!(app.labels.iter().all(|x| x.is_visible && x.hotkey.is_none()));
+    assert!(app.labels[0].is_active && app.labels[1].name == "ORG");
+}
+
+#[test]
+fn test_modal_size() {
+    let mut app = crate::io::load_test_app("alpha", &[(0, 5, "PER")]);
+    assert!(app.get_modal_size() == (40, 21));
+
+    app.set_window(crossterm::terminal::WindowSize {
+        rows: 10,
+        columns: 30,
+        width: 0,
+        height: 0,
+    });
+    assert!(app.get_modal_size() == (28, 7) && app.modal_start_column == 0);
+}
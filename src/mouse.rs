@@ -0,0 +1,91 @@
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+
+use crate::{app::App, common};
+
+const SCROLL_LINES: usize = 3;
+
+impl App {
+    pub fn dispatch_mouse(&mut self, event: MouseEvent) {
+        if !(self.is_normal_mode() || self.is_visual_mode() || self.is_review_mode()) {
+            return;
+        }
+
+        let (column, row) = (event.column as usize, event.row as usize);
+
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => self.mouse_down(column, row),
+            MouseEventKind::Drag(MouseButton::Left) => self.mouse_drag(column, row),
+            MouseEventKind::ScrollDown => self.mouse_scroll(true),
+            MouseEventKind::ScrollUp => self.mouse_scroll(false),
+            _ => (),
+        }
+    }
+
+    fn mouse_down(&mut self, column: usize, row: usize) {
+        if self.is_review_mode() {
+            return;
+        }
+
+        if let Some((row, column)) = self.get_mouse_position(column, row) {
+            if self.is_visual_mode() {
+                self.visual.clear();
+                self.change |= 0b0011;
+            }
+
+            self.set_normal_mode();
+            common::move_to_position(self, row, column);
+            self.change |= 0b0001;
+        }
+    }
+
+    fn mouse_drag(&mut self, column: usize, row: usize) {
+        if self.is_review_mode() {
+            return;
+        }
+
+        let anchor = if self.is_visual_mode() {
+            self.get_visual_anchor()
+        } else {
+            (self.cursor_row + self.offset_row, self.cursor_column)
+        };
+
+        if let Some((row, column)) = self.get_mouse_position(column, row) {
+            self.set_visual_mode();
+            common::move_to_position(self, row, column);
+            self.set_visual_range(anchor);
+        }
+    }
+
+    fn mouse_scroll(&mut self, is_down: bool) {
+        let anchor = self.get_visual_anchor();
+
+        if is_down {
+            common::handle_scroll_down(self, SCROLL_LINES);
+        } else {
+            common::handle_scroll_up(self, SCROLL_LINES);
+        }
+
+        if self.is_visual_mode() {
+            self.set_visual_range(anchor);
+        }
+    }
+
+    fn get_mouse_position(&self, column: usize, row: usize) -> Option<(usize, usize)> {
+        if row >= self.window_height.saturating_sub(1) || row + self.offset_row >= self.nlines {
+            return None;
+        }
+
+        let line = &self.lines[row + self.offset_row];
+        let column = column.saturating_sub(self.get_left_margin());
+        let column = if line.is_virtual {
+            column.saturating_sub(2)
+        } else {
+            column + self.offset_column
+        };
+
+        Some((
+            row + self.offset_row,
+            std::cmp::min(column, line.width.saturating_sub(1)),
+        ))
+    }
+}
@@ -6,37 +6,71 @@ impl App {
     }
 
     pub fn set_name_mode(&mut self) {
-        self.modal_column = self.modal_start_column + self.labels[self.modal_row].name.len() + 19;
+        self.name.set(&self.labels[self.modal_row].name);
+        self.modal_column = self.modal_start_column + self.name.get_cursor_width() + 19;
         self.mode = Mode::Name;
     }
 
-    fn get_active_label_name(&mut self) -> &mut String {
-        &mut self.labels[self.modal_row].name
+    fn set_active_label_name(&mut self, is_changed: bool) {
+        if is_changed {
+            self.name.truncate(self.settings.name_limit);
+            self.is_dirty |= self.labels[self.modal_row].name != self.name.text;
+            self.labels[self.modal_row].name = self.name.text.clone();
+            self.modal_column = self.modal_start_column + self.name.get_cursor_width() + 19;
+            self.change |= 0b_0001_1001;
+        }
     }
 
     pub fn name_backspace(&mut self) {
-        let name = self.get_active_label_name();
-
-        if !name.is_empty() {
-            name.pop();
+        let is_changed = self.name.backspace();
+        self.set_active_label_name(is_changed);
+    }
 
-            self.modal_column -= 1;
-            self.change |= 0b_0001_1001;
+    pub fn name_char(&mut self, c: char) {
+        if self.name.text.chars().count() < self.settings.name_limit {
+            self.name.insert(c);
+            self.set_active_label_name(true);
         }
     }
 
-    pub fn name_char(&mut self, c: char) {
-        let name = self.get_active_label_name();
+    pub fn name_left(&mut self) {
+        let is_changed = self.name.left();
+        self.set_active_label_name(is_changed);
+    }
 
-        if name.len() < 20 {
-            name.push(c);
+    pub fn name_right(&mut self) {
+        let is_changed = self.name.right();
+        self.set_active_label_name(is_changed);
+    }
 
-            self.modal_column += 1;
-            self.change |= 0b_0001_1001;
-        }
+    pub fn name_up(&mut self) {
+        let is_changed = self.name.history_prev();
+        self.set_active_label_name(is_changed);
+    }
+
+    pub fn name_down(&mut self) {
+        let is_changed = self.name.history_next();
+        self.set_active_label_name(is_changed);
+    }
+
+    pub fn name_delete_line(&mut self) {
+        let is_changed = self.name.delete_line();
+        self.set_active_label_name(is_changed);
+    }
+
+    pub fn name_delete_word(&mut self) {
+        let is_changed = self.name.delete_word();
+        self.set_active_label_name(is_changed);
+    }
+
+    pub fn name_tab(&mut self) {
+        let candidates = self.labels.iter().map(|x| x.name.clone()).collect::<Vec<String>>();
+        let is_changed = self.name.complete(&candidates);
+        self.set_active_label_name(is_changed);
     }
 
     pub fn name_esc(&mut self) {
+        self.name.push_history();
         self.set_modal_mode();
         self.change |= 0b_0001_1011;
     }
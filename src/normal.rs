@@ -1,6 +1,7 @@
 use crate::{
     app::{App, Mode, Visual},
-    common,
+    common::{self, TagFilter, TextObject},
+    keymap::{self, Action, Motion},
 };
 
 impl App {
@@ -12,11 +13,47 @@ impl App {
         self.mode = Mode::Normal;
     }
 
-    pub fn normal_m(&mut self) {
-        self.set_modal_mode();
+    #[allow(non_snake_case)]
+    pub fn normal_T(&mut self) {
+        self.open_modal();
         self.change = 0b_0001_1000;
     }
 
+    pub fn normal_question(&mut self) {
+        self.open_help("");
+    }
+
+    pub fn normal_mark(&mut self) {
+        if self.argument.is_ascii_lowercase() {
+            let position = self.get_absolute_position(self.cursor_row + self.offset_row, self.cursor_column);
+            self.marks.insert(self.argument, position);
+        } else {
+            self.message = format!("Invalid mark: {}", self.argument);
+            self.change |= 0b0001;
+        }
+    }
+
+    pub fn normal_quote(&mut self) {
+        match self.marks.get(&self.argument) {
+            Some(&position) => {
+                common::push_jump(self);
+                common::move_to_absolute(self, position);
+            }
+            None => {
+                self.message = format!("Mark not set: {}", self.argument);
+                self.change |= 0b0001;
+            }
+        }
+    }
+
+    pub fn normal_jump_older(&mut self) {
+        common::handle_jump_older(self);
+    }
+
+    pub fn normal_jump_newer(&mut self) {
+        common::handle_jump_newer(self);
+    }
+
     pub fn normal_h(&mut self) {
         common::handle_h(self);
     }
@@ -37,16 +74,101 @@ impl App {
         common::handle_s(self);
     }
 
-    pub fn normal_e(&mut self) {
-        common::handle_e(self);
+    pub fn normal_dollar(&mut self) {
+        common::handle_dollar(self);
     }
 
     pub fn normal_w(&mut self) {
-        common::handle_w(self);
+        common::handle_w(self, false);
+    }
+
+    #[allow(non_snake_case)]
+    pub fn normal_W(&mut self) {
+        common::handle_w(self, true);
+    }
+
+    pub fn normal_e(&mut self) {
+        common::handle_e(self, false);
+    }
+
+    #[allow(non_snake_case)]
+    pub fn normal_end_WORD(&mut self) {
+        common::handle_e(self, true);
     }
 
     pub fn normal_b(&mut self) {
-        common::handle_b(self);
+        common::handle_b(self, false);
+    }
+
+    #[allow(non_snake_case)]
+    pub fn normal_B(&mut self) {
+        common::handle_b(self, true);
+    }
+
+    pub fn normal_ge(&mut self) {
+        common::handle_ge(self, false);
+    }
+
+    #[allow(non_snake_case)]
+    pub fn normal_gE(&mut self) {
+        common::handle_ge(self, true);
+    }
+
+    pub fn normal_f(&mut self) {
+        common::handle_find(self, 'f');
+    }
+
+    #[allow(non_snake_case)]
+    pub fn normal_F(&mut self) {
+        common::handle_find(self, 'F');
+    }
+
+    pub fn normal_gt(&mut self) {
+        common::handle_find(self, 't');
+    }
+
+    #[allow(non_snake_case)]
+    pub fn normal_gT(&mut self) {
+        common::handle_find(self, 'T');
+    }
+
+    pub fn normal_semicolon(&mut self) {
+        common::handle_find_repeat(self, false);
+    }
+
+    pub fn normal_comma(&mut self) {
+        common::handle_find_repeat(self, true);
+    }
+
+    pub fn normal_n(&mut self) {
+        common::push_jump(self);
+        common::handle_tag_next(self, TagFilter::Any);
+    }
+
+    #[allow(non_snake_case)]
+    pub fn normal_N(&mut self) {
+        common::push_jump(self);
+        common::handle_tag_prev(self, TagFilter::Any);
+    }
+
+    pub fn normal_active_next(&mut self) {
+        common::push_jump(self);
+        common::handle_tag_next(self, TagFilter::Active);
+    }
+
+    pub fn normal_active_prev(&mut self) {
+        common::push_jump(self);
+        common::handle_tag_prev(self, TagFilter::Active);
+    }
+
+    pub fn normal_overlap_next(&mut self) {
+        common::push_jump(self);
+        common::handle_tag_next(self, TagFilter::Overlap);
+    }
+
+    pub fn normal_overlap_prev(&mut self) {
+        common::push_jump(self);
+        common::handle_tag_prev(self, TagFilter::Overlap);
     }
 
     pub fn normal_v(&mut self) {
@@ -63,8 +185,127 @@ impl App {
         self.change = 0b0001;
     }
 
+    #[allow(non_snake_case)]
+    pub fn normal_V(&mut self) {
+        self.normal_v();
+        self.is_linewise = true;
+        self.set_visual_range((self.cursor_row + self.offset_row, self.cursor_column));
+    }
+
+    pub fn normal_r(&mut self) {
+        self.set_review_mode();
+        self.review_seek(true, true, true);
+    }
+
+    pub fn normal_t(&mut self) {
+        self.operator = Some('t');
+        self.change |= 0b0001;
+    }
+
     pub fn normal_u(&mut self) {
-        self.untag();
+        self.operator = Some('u');
+        self.change |= 0b0001;
+    }
+
+    pub fn normal_iw(&mut self) {
+        self.select_operator_object(TextObject::Word, true);
+    }
+
+    pub fn normal_aw(&mut self) {
+        self.select_operator_object(TextObject::Word, false);
+    }
+
+    pub fn normal_is(&mut self) {
+        self.select_operator_object(TextObject::Sentence, true);
+    }
+
+    pub fn normal_as(&mut self) {
+        self.select_operator_object(TextObject::Sentence, false);
+    }
+
+    pub fn normal_ip(&mut self) {
+        self.select_operator_object(TextObject::Paragraph, true);
+    }
+
+    pub fn normal_iquote(&mut self) {
+        self.select_operator_object(TextObject::Quote, true);
+    }
+
+    fn select_operator_object(&mut self, object: TextObject, is_inner: bool) {
+        if self.operator.is_some() {
+            self.select_text_object(object, is_inner);
+        }
+    }
+
+    pub fn apply_operator(&mut self, name: &str, action: Action) {
+        let row = self.cursor_row + self.offset_row;
+        let anchor = self.get_absolute_position(row, self.cursor_column);
+
+        self.visual.clear();
+        self.change |= 0b0001;
+
+        if self.operator_count > 0 {
+            self.count = std::cmp::max(self.count, 1).saturating_mul(self.operator_count);
+            self.operator_count = 0;
+        }
+
+        let range = match (self.operator, name) {
+            (Some('t'), "normal_t") | (Some('u'), "normal_u") => {
+                let last = std::cmp::min(row + std::cmp::max(self.count, 1) - 1, self.nlines - 1);
+                Some(self.get_line_range(row, last))
+            }
+            (_, "normal_t" | "normal_u") => None,
+            _ => {
+                self.common_repeat(action);
+
+                let cursor_row = self.cursor_row + self.offset_row;
+                let cursor = self.get_absolute_position(cursor_row, self.cursor_column);
+                let (start, end) = (std::cmp::min(anchor, cursor), std::cmp::max(anchor, cursor));
+
+                match self.get_visual_range() {
+                    _ if !self.is_normal_mode() => None,
+                    Some(range) => Some(range),
+                    None => match keymap::get_motion(name, cursor > anchor) {
+                        Motion::Exclusive => Some((start, end)),
+                        Motion::Inclusive => Some((start, end + self.get_char_width(end))),
+                        Motion::Linewise => {
+                            Some(self.get_line_range(std::cmp::min(row, cursor_row), std::cmp::max(row, cursor_row)))
+                        }
+                    },
+                }
+            }
+        };
+
+        self.visual.clear();
+        self.count = 0;
+
+        match (self.operator.take(), range) {
+            (Some('t'), Some((start, end))) => self.tag_range(start, end),
+            (Some('u'), Some((start, end))) => self.untag_range(start, end, None, true),
+            _ => return,
+        }
+
+        if let Some((start, _)) = range {
+            let (row, column) = self.find_absolute_position(start);
+            common::move_to_position(self, row, column);
+        }
+    }
+
+    fn get_line_range(&self, first: usize, last: usize) -> (usize, usize) {
+        (
+            self.lines[first].absolute_offset,
+            self.lines[last].absolute_offset + self.lines[last].width,
+        )
+    }
+
+    fn get_char_width(&self, position: usize) -> usize {
+        let (row, column) = self.find_absolute_position(position);
+
+        self.lines[row]
+            .text
+            .get(column..)
+            .and_then(|x| x.chars().next())
+            .map_or(1, char::len_utf8)
     }
 
     pub fn normal_pg_down(&mut self) {
@@ -75,28 +316,69 @@ impl App {
         common::handle_pg_up(self);
     }
 
+    pub fn normal_scroll_down(&mut self) {
+        common::handle_scroll_down(self, 1);
+    }
+
+    pub fn normal_scroll_up(&mut self) {
+        common::handle_scroll_up(self, 1);
+    }
+
     #[allow(non_snake_case)]
     pub fn normal_H(&mut self) {
+        common::push_jump(self);
         common::handle_H(self);
     }
 
     #[allow(non_snake_case)]
     pub fn normal_M(&mut self) {
+        common::push_jump(self);
         common::handle_M(self);
     }
 
     #[allow(non_snake_case)]
     pub fn normal_L(&mut self) {
+        common::push_jump(self);
         common::handle_L(self);
     }
 
     #[allow(non_snake_case)]
-    pub fn normal_E(&mut self) {
+    pub fn normal_G(&mut self) {
+        common::push_jump(self);
         common::handle_E(self);
     }
 
     #[allow(non_snake_case)]
     pub fn normal_S(&mut self) {
+        common::push_jump(self);
         common::handle_S(self);
     }
 }
+
+#[test]
+fn test_operator_ranges() {
+    let tag = |keys: &str| {
+        let mut app = crate::io::load_test_app("alpha beta gamma delta\nepsilon\nzeta", &[(31, 35, "PER")]);
+        keys.chars().for_each(|x| app.dispatch(x));
+
+        app.lines[..2]
+            .iter()
+            .enumerate()
+            .flat_map(|(i, x)| x.tags.iter().map(move |y| (i, y.start, y.end)))
+            .collect::<Vec<(usize, usize, usize)>>()
+    };
+
+    assert!(tag("tw") == [(0, 0, 5)]);
+    assert!(tag("t2w") == [(0, 0, 10)]);
+    assert!(tag("3tw") == [(0, 0, 16)]);
+    assert!(tag("2t2w") == [(0, 0, 22)]);
+    assert!(tag("te") == [(0, 0, 5)]);
+    assert!(tag("tfe") == [(0, 0, 8)]);
+    assert!(tag("wt$") == [(0, 6, 22)]);
+    assert!(tag("wtiw") == [(0, 6, 10)]);
+    assert!(tag("w2taw") == [(0, 6, 16)]);
+    assert!(tag("tt") == [(0, 0, 22)]);
+    assert!(tag("2tt") == [(0, 0, 22), (1, 0, 7)]);
+    assert!(tag("tj") == [(0, 0, 22), (1, 0, 7)]);
+    assert!(tag("viwvwtw") == [(0, 6, 10)]);
+}
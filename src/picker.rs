@@ -0,0 +1,144 @@
+use crate::app::{App, Mode};
+
+const PICKER_ROWS: usize = 8;
+
+impl App {
+    pub fn is_picker_mode(&self) -> bool {
+        self.mode == Mode::Picker
+    }
+
+    pub fn set_picker_mode(&mut self) {
+        self.mode = Mode::Picker;
+    }
+
+    pub fn open_picker(&mut self) {
+        self.picker.clear();
+        self.picker_row = 0;
+        self.set_picker_mode();
+        self.change |= 0b_1000_1000;
+    }
+
+    pub fn get_picker_rows(&self) -> Vec<usize> {
+        let mut rows = self
+            .labels
+            .iter()
+            .enumerate()
+            .filter_map(|(i, x)| fuzzy_match(&self.picker.text, &x.name).map(|score| (score, i)))
+            .collect::<Vec<(usize, usize)>>();
+
+        rows.sort_by_key(|&(score, i)| (score, std::cmp::Reverse(self.labels[i].last_used), i));
+        rows.into_iter().map(|(_, i)| i).collect()
+    }
+
+    pub fn get_picker_size(&self) -> (usize, usize) {
+        let width = self.labels.iter().map(|x| x.name.chars().count()).max().unwrap_or(0) + 6;
+        let height = std::cmp::min(self.labels.len(), PICKER_ROWS) + 3;
+
+        (std::cmp::min(std::cmp::max(width, 16), self.window_width), height)
+    }
+
+    fn set_picker_query(&mut self, is_changed: bool) {
+        if is_changed {
+            self.picker_row = 0;
+            self.change |= 0b_1000_1000;
+        }
+    }
+
+    pub fn picker_char(&mut self, c: char) {
+        let (width, _) = self.get_picker_size();
+
+        if self.picker.text.chars().count() + 5 < width {
+            self.picker.insert(c);
+            self.set_picker_query(true);
+        }
+    }
+
+    pub fn picker_backspace(&mut self) {
+        let is_changed = self.picker.backspace();
+        self.set_picker_query(is_changed);
+    }
+
+    pub fn picker_left(&mut self) {
+        if self.picker.left() {
+            self.change |= 0b_1000_1000;
+        }
+    }
+
+    pub fn picker_right(&mut self) {
+        if self.picker.right() {
+            self.change |= 0b_1000_1000;
+        }
+    }
+
+    pub fn picker_delete_line(&mut self) {
+        let is_changed = self.picker.delete_line();
+        self.set_picker_query(is_changed);
+    }
+
+    pub fn picker_delete_word(&mut self) {
+        let is_changed = self.picker.delete_word();
+        self.set_picker_query(is_changed);
+    }
+
+    pub fn picker_down(&mut self) {
+        let nrows = self.get_picker_rows().len();
+
+        if nrows > 0 {
+            self.picker_row = (self.picker_row + 1) % nrows;
+            self.change |= 0b_1000_0000;
+        }
+    }
+
+    pub fn picker_up(&mut self) {
+        let nrows = self.get_picker_rows().len();
+
+        if nrows > 0 {
+            self.picker_row = (self.picker_row + nrows - 1) % nrows;
+            self.change |= 0b_1000_0000;
+        }
+    }
+
+    pub fn picker_return(&mut self) {
+        match self.get_picker_rows().get(self.picker_row) {
+            Some(&label) => {
+                self.tag_with(label);
+                self.leave_visual_mode();
+                self.change |= 0b_0000_1011;
+            }
+            None => {
+                self.message = format!("No label matching: {}", self.picker.text);
+                self.change |= 0b0001;
+            }
+        }
+    }
+
+    pub fn picker_esc(&mut self) {
+        self.mode = Mode::Visual;
+        self.change |= 0b_0000_1011;
+    }
+}
+
+/// Scores `name` against `query` as a case-insensitive subsequence, lower is better.
+pub fn fuzzy_match(query: &str, name: &str) -> Option<usize> {
+    let name = name.to_lowercase().chars().collect::<Vec<char>>();
+    let mut score = 0;
+    let mut position = 0;
+
+    for (i, c) in query.to_lowercase().chars().enumerate() {
+        let offset = name[position..].iter().position(|&x| x == c)?;
+
+        score += if i == 0 { offset * 2 } else { offset };
+        position += offset + 1;
+    }
+
+    Some(score)
+}
+
+#[test]
+fn test_fuzzy_match() {
+    assert!(fuzzy_match("", "PERSON") == Some(0));
+    assert!(fuzzy_match("per", "PERSON") == Some(0));
+    assert!(fuzzy_match("psn", "PERSON").is_some());
+    assert!(fuzzy_match("org", "PERSON").is_none());
+    assert!(fuzzy_match("loc", "LOC") < fuzzy_match("loc", "GPE_LOCATION"));
+}
@@ -2,7 +2,7 @@ use std::io::{Stdout, Write};
 
 use anyhow::Result;
 use crossterm::{
-    cursor, queue,
+    cursor, event, queue,
     style::{self, Color},
     terminal::{self, ClearType},
 };
@@ -10,7 +10,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     app::{self, App, Label, Line},
-    helper,
+    common, help, helper, keymap,
 };
 
 #[derive(Debug, PartialEq)]
@@ -20,6 +20,8 @@ enum Change {
     Offset,
     Status,
     Modal,
+    Help,
+    Picker,
 }
 
 #[derive(Clone)]
@@ -40,6 +42,7 @@ pub fn render_initial(app: &mut App, stdout: &mut Stdout) -> Result<()> {
     terminal::enable_raw_mode()?;
 
     queue!(stdout, terminal::EnterAlternateScreen)?;
+    queue!(stdout, event::EnableMouseCapture)?;
     queue!(stdout, helper::move_to(app.cursor_column, app.cursor_row))?;
 
     render_offset(app, stdout)?;
@@ -49,6 +52,7 @@ pub fn render_initial(app: &mut App, stdout: &mut Stdout) -> Result<()> {
 }
 
 pub fn render_terminal(stdout: &mut Stdout) {
+    queue!(stdout, event::DisableMouseCapture).expect("Error disabling mouse capture");
     queue!(stdout, terminal::LeaveAlternateScreen).expect("Error leaving alternate screen");
     queue!(stdout, cursor::Show).expect("Error showing cursor");
 
@@ -57,6 +61,9 @@ pub fn render_terminal(stdout: &mut Stdout) {
 }
 
 pub fn render_event(app: &mut App, stdout: &mut Stdout) -> Result<()> {
+    common::manage_scrolloff(app);
+    common::manage_horizontal_scroll(app);
+
     let flags = get_change_flags(app);
     app.change = 0;
 
@@ -64,6 +71,8 @@ pub fn render_event(app: &mut App, stdout: &mut Stdout) -> Result<()> {
         match flag {
             Change::Cursor => render_cursor(app, stdout)?,
             Change::Modal => render_modal(app, stdout)?,
+            Change::Help => render_help(app, stdout)?,
+            Change::Picker => render_picker(app, stdout)?,
             Change::Offset => render_offset(app, stdout)?,
             Change::Command => render_command(app, stdout)?,
             Change::Status => render_status(app, stdout)?,
@@ -82,6 +91,8 @@ pub fn render_event(app: &mut App, stdout: &mut Stdout) -> Result<()> {
 
         ^ app.modal
        ^ app.command
+      ^ app.help
+     ^ app.picker
 */
 fn get_change_flags(app: &mut App) -> Vec<Change> {
     let mut flags = Vec::new();
@@ -92,6 +103,12 @@ fn get_change_flags(app: &mut App) -> Vec<Change> {
     if app.change & 0b_0001_0000 > 0 {
         flags.push(Change::Modal);
     }
+    if app.change & 0b_0100_0000 > 0 && app.is_help_mode() {
+        flags.push(Change::Help);
+    }
+    if app.change & 0b_1000_0000 > 0 && app.is_picker_mode() {
+        flags.push(Change::Picker);
+    }
     if app.change & 0b_0000_0001 > 0 {
         flags.push(Change::Status);
     }
@@ -106,21 +123,35 @@ fn get_change_flags(app: &mut App) -> Vec<Change> {
 }
 
 fn render_cursor(app: &App, stdout: &mut Stdout) -> Result<()> {
-    if app.is_name_mode() {
+    if app.is_command_mode() {
+        queue!(
+            stdout,
+            helper::move_to(app.command.get_cursor_width() + 1, app.window_height - 1)
+        )?;
+    } else if app.is_name_mode() {
         queue!(
             stdout,
-            helper::move_to(app.modal_column, app.modal_start_row + app.modal_row + 1)
+            helper::move_to(app.modal_column, app.modal_start_row + app.get_modal_line())
+        )?;
+    } else if app.is_picker_mode() {
+        let (column, row) = get_picker_position(app);
+        queue!(
+            stdout,
+            helper::move_to(column + app.picker.get_cursor_width() + 3, row + 1)
+        )?;
+    } else if app.is_filter_mode() {
+        queue!(
+            stdout,
+            helper::move_to(
+                app.modal_start_column + app.modal_filter.get_cursor_width() + 4,
+                app.modal_start_row
+            )
         )?;
     } else {
-        let cursor_column = if app.get_current_line().is_virtual {
-            app.cursor_column + 2
-        } else {
-            app.cursor_column
-        };
-        queue!(stdout, helper::move_to(cursor_column, app.cursor_row))?;
+        queue!(stdout, helper::move_to(get_cursor_column(app), app.cursor_row))?;
     }
 
-    if app.is_modal_mode() {
+    if app.is_modal_mode() || app.is_help_mode() {
         queue!(stdout, cursor::Hide).map_err(anyhow::Error::from)
     } else {
         queue!(stdout, cursor::Show).map_err(anyhow::Error::from)
@@ -129,6 +160,7 @@ fn render_cursor(app: &App, stdout: &mut Stdout) -> Result<()> {
 
 fn render_modal(app: &mut App, stdout: &mut Stdout) -> Result<()> {
     let lines = chunk_modal_lines(app);
+    let modal_line = app.get_modal_line();
     queue!(stdout, helper::move_to(app.modal_start_column, app.modal_start_row))?;
 
     for (i, line) in lines.iter().enumerate() {
@@ -136,7 +168,7 @@ fn render_modal(app: &mut App, stdout: &mut Stdout) -> Result<()> {
             queue!(
                 stdout,
                 style::SetBackgroundColor(chunk.color),
-                style::SetForegroundColor(if i == app.modal_row + 1 && chunk.is_name {
+                style::SetForegroundColor(if i == modal_line && chunk.is_name {
                     Color::Yellow
                 } else {
                     Color::White
@@ -152,14 +184,128 @@ fn render_modal(app: &mut App, stdout: &mut Stdout) -> Result<()> {
     Ok(())
 }
 
-fn render_offset(app: &App, stdout: &mut Stdout) -> Result<()> {
+fn render_help(app: &mut App, stdout: &mut Stdout) -> Result<()> {
+    let lines = help::get_help_lines(&app.keymap, &app.settings);
+    let query = app.help_query.to_lowercase();
+
+    let (width, height) = app.get_help_size();
+    let column = (app.window_width - width) / 2;
+    let inner = width.saturating_sub(6);
+
+    queue!(
+        stdout,
+        helper::move_to(column, 1),
+        style::SetBackgroundColor(Color::Reset),
+        style::SetForegroundColor(Color::White),
+        style::Print(format!(" ┌{:─>width$}┐ ", "", width = width.saturating_sub(4))),
+    )?;
+
+    for i in 0..height.saturating_sub(2) {
+        let line = lines.get(app.help_row + i).map_or("", |x| x.as_str());
+        let text = line.chars().take(inner).collect::<String>();
+
+        queue!(
+            stdout,
+            helper::move_to(column, i + 2),
+            style::Print(" │ "),
+            style::SetForegroundColor(if !query.is_empty() && line.to_lowercase().contains(&query) {
+                Color::Yellow
+            } else {
+                Color::White
+            }),
+            style::Print(format!("{:inner$}", text)),
+            style::SetForegroundColor(Color::White),
+            style::Print(" │ "),
+        )?;
+    }
+
+    queue!(
+        stdout,
+        helper::move_to(column, height),
+        style::Print(format!(" └{:─>width$}┘ ", "", width = width.saturating_sub(4))),
+    )
+    .map_err(anyhow::Error::from)
+}
+
+fn render_picker(app: &mut App, stdout: &mut Stdout) -> Result<()> {
+    let rows = app.get_picker_rows();
+    let (width, height) = app.get_picker_size();
+    let (column, row) = get_picker_position(app);
+
+    let inner = width.saturating_sub(6);
+    let visible = height - 3;
+    let offset = (app.picker_row + 1).saturating_sub(visible);
+
+    queue!(
+        stdout,
+        helper::move_to(column, row),
+        style::SetBackgroundColor(Color::Reset),
+        style::SetForegroundColor(Color::White),
+        style::Print(format!("┌{:─>width$}┐", "", width = width - 2)),
+        helper::move_to(column, row + 1),
+        style::Print(format!("│ >{:inner$} │", app.picker.text, inner = inner + 1)),
+    )?;
+
+    for i in 0..visible {
+        let label = rows.get(offset + i).map(|&x| &app.labels[x]);
+        let is_current = offset + i == app.picker_row && label.is_some();
+
+        queue!(
+            stdout,
+            helper::move_to(column, row + i + 2),
+            style::SetBackgroundColor(Color::Reset),
+            style::Print("│"),
+            style::SetBackgroundColor(label.map_or(Color::Reset, |x| x.color)),
+            style::Print(" "),
+            style::SetBackgroundColor(Color::Reset),
+            style::SetForegroundColor(if is_current { Color::Yellow } else { Color::White }),
+            style::Print(format!(
+                " {:inner$.inner$}  ",
+                label.map_or("", |x| x.name.as_str()),
+                inner = inner
+            )),
+            style::SetForegroundColor(Color::White),
+            style::Print("│"),
+        )?;
+    }
+
+    queue!(
+        stdout,
+        helper::move_to(column, row + height - 1),
+        style::Print(format!("└{:─>width$}┘", "", width = width - 2)),
+    )
+    .map_err(anyhow::Error::from)
+}
+
+fn get_picker_position(app: &App) -> (usize, usize) {
+    let (width, height) = app.get_picker_size();
+    let column = std::cmp::min(get_cursor_column(app), app.window_width.saturating_sub(width));
+
+    let row = if app.cursor_row + 1 + height < app.window_height {
+        app.cursor_row + 1
+    } else {
+        app.cursor_row.saturating_sub(height)
+    };
+
+    (column, row)
+}
+
+fn render_offset(app: &App, stdout: &mut impl Write) -> Result<()> {
     let start = app.offset_row;
     let end = std::cmp::min(app.window_height + app.offset_row - 1, app.nlines);
 
     // queue!(stdout, cursor::Show)?;
 
+    let margin = app.get_left_margin();
+    let width = app.window_width.saturating_sub(margin + 1);
+
     for line in &app.lines[start..end] {
-        queue!(stdout, helper::move_to(0, line.virtual_row - app.offset_row))?;
+        queue!(
+            stdout,
+            helper::move_to(0, line.virtual_row - app.offset_row),
+            style::SetBackgroundColor(Color::Reset),
+            style::Print(format!("{:margin$}", "")),
+        )?;
 
         if line.is_virtual {
             queue!(
@@ -171,12 +317,28 @@ fn render_offset(app: &App, stdout: &mut Stdout) -> Result<()> {
         }
 
         for chunk in chunk_line(line, app) {
-            let text = &line.text[chunk.start..chunk.end];
+            let mut start = std::cmp::max(chunk.start, app.offset_column);
+            let mut end = std::cmp::min(chunk.end, app.offset_column + width);
+
+            // The horizontal offset is shared by every line, so it may fall inside a character of this one.
+            while start < end && !line.text.is_char_boundary(start) {
+                start += 1;
+            }
+
+            while end > start && !line.text.is_char_boundary(end) {
+                end -= 1;
+            }
+
+            if start >= end {
+                continue;
+            }
+
+            let text = &line.text[start..end];
 
             queue!(
                 stdout,
                 helper::move_to(
-                    chunk.start + if line.is_virtual { 2 } else { 0 },
+                    margin + start - app.offset_column + if line.is_virtual { 2 } else { 0 },
                     line.virtual_row - app.offset_row
                 ),
                 style::SetForegroundColor(Color::White),
@@ -188,13 +350,26 @@ fn render_offset(app: &App, stdout: &mut Stdout) -> Result<()> {
         queue!(stdout, terminal::Clear(ClearType::UntilNewLine))?;
     }
 
-    let cursor_column = if app.get_current_line().is_virtual {
-        app.cursor_column + 2
-    } else {
-        app.cursor_column
-    };
+    for row in end - start..app.window_height - 1 {
+        queue!(
+            stdout,
+            helper::move_to(0, row),
+            style::SetBackgroundColor(Color::Reset),
+            terminal::Clear(ClearType::UntilNewLine),
+        )?;
+    }
 
-    queue!(stdout, helper::move_to(cursor_column, app.cursor_row)).map_err(anyhow::Error::from)
+    queue!(stdout, helper::move_to(get_cursor_column(app), app.cursor_row)).map_err(anyhow::Error::from)
+}
+
+fn get_cursor_column(app: &App) -> usize {
+    let cursor_column = app.get_left_margin() + app.cursor_column.saturating_sub(app.offset_column);
+
+    if app.get_current_line().is_virtual {
+        cursor_column + 2
+    } else {
+        cursor_column
+    }
 }
 
 fn clear_status(app: &mut App, stdout: &mut Stdout) -> Result<()> {
@@ -210,11 +385,20 @@ fn clear_status(app: &mut App, stdout: &mut Stdout) -> Result<()> {
 fn render_status(app: &mut App, stdout: &mut Stdout) -> Result<()> {
     clear_status(app, stdout)?;
 
-    if app.is_visual_mode() {
+    if !app.message.is_empty() {
         queue!(
             stdout,
             helper::move_to(0, app.window_height - 1),
-            style::SetBackgroundColor(Color::AnsiValue(172)),
+            style::SetBackgroundColor(Color::Reset),
+            style::SetForegroundColor(Color::Red),
+            style::Print(app.message.chars().take(app.window_width - 1).collect::<String>()),
+            style::SetForegroundColor(Color::White),
+        )?;
+    } else if app.is_visual_mode() {
+        queue!(
+            stdout,
+            helper::move_to(0, app.window_height - 1),
+            style::SetBackgroundColor(Color::AnsiValue(app.settings.visual_color)),
             style::Print("      "),
         )?;
     } else {
@@ -244,19 +428,45 @@ fn render_status(app: &mut App, stdout: &mut Stdout) -> Result<()> {
             queue!(
                 stdout,
                 helper::move_to(0, app.window_height - 1),
-                style::SetBackgroundColor(Color::AnsiValue(160)),
+                style::SetBackgroundColor(Color::AnsiValue(app.settings.overlap_color)),
                 style::Print("      "),
             )?;
         }
     }
 
-    let status = format!(
+    let mut status = format!(
         "{}% {}:{}",
         (app.cursor_row + app.offset_row) * 100 / app.nlines,
         app.cursor_row + app.offset_row,
         app.cursor_column,
     );
 
+    if app.is_dirty {
+        status.insert_str(0, "[+]  ");
+    }
+
+    if app.is_review_mode() {
+        let (reviewed, total) = app.get_review_progress();
+        status.insert_str(0, &format!("review {}/{}  ", reviewed, total));
+    }
+
+    if app.count > 0 || !app.pending.is_empty() || app.operator.is_some() {
+        let operator = match app.operator {
+            Some(operator) if app.operator_count > 0 => format!("{}{}", app.operator_count, operator),
+            Some(operator) => operator.to_string(),
+            None => String::new(),
+        };
+        let count = if app.count > 0 {
+            app.count.to_string()
+        } else {
+            String::new()
+        };
+        status.insert_str(
+            0,
+            &format!("{}{}{}  ", operator, count, keymap::format_keys(&app.pending)),
+        );
+    }
+
     queue!(
         stdout,
         helper::move_to(app.window_width - status.len() - 1, app.window_height - 1),
@@ -264,14 +474,7 @@ fn render_status(app: &mut App, stdout: &mut Stdout) -> Result<()> {
         style::Print(status),
     )?;
 
-    queue!(
-        stdout,
-        helper::move_to(
-            app.cursor_column + if app.get_current_line().is_virtual { 2 } else { 0 },
-            app.cursor_row
-        )
-    )
-    .map_err(anyhow::Error::from)
+    queue!(stdout, helper::move_to(get_cursor_column(app), app.cursor_row)).map_err(anyhow::Error::from)
 }
 
 fn render_command(app: &mut App, stdout: &mut Stdout) -> Result<()> {
@@ -281,7 +484,8 @@ fn render_command(app: &mut App, stdout: &mut Stdout) -> Result<()> {
         stdout,
         helper::move_to(0, app.window_height - 1),
         style::SetBackgroundColor(Color::Reset),
-        style::Print(format!(":{}", app.command)),
+        style::Print(format!(":{}", app.command.text)),
+        helper::move_to(app.command.get_cursor_width() + 1, app.window_height - 1),
     )
     .map_err(anyhow::Error::from)
 }
@@ -290,6 +494,7 @@ fn chunk_line(line: &Line, app: &App) -> Vec<OffsetChunk> {
     let mut points = vec![0, line.width];
 
     let (visual_start, visual_end) = app.get_visual_bounds(line.virtual_row);
+    let visual_end = std::cmp::min(visual_end, line.width);
     points.extend([visual_start, visual_end]);
 
     let tag_points = line.tags.iter().flat_map(|x| [x.start, x.end]);
@@ -311,9 +516,9 @@ fn chunk_line(line: &Line, app: &App) -> Vec<OffsetChunk> {
                 .collect::<Vec<usize>>();
 
             let color = if visual_start <= s && *e <= visual_end {
-                Color::AnsiValue(172)
+                Color::AnsiValue(app.settings.visual_color)
             } else if tags.len() > 1 {
-                Color::AnsiValue(160)
+                Color::AnsiValue(app.settings.overlap_color)
             } else if let [tag] = tags[..] {
                 app.labels[tag].color
             } else {
@@ -332,27 +537,57 @@ fn chunk_line(line: &Line, app: &App) -> Vec<OffsetChunk> {
 }
 
 fn chunk_modal_lines(app: &mut App) -> Vec<Vec<ModalChunk>> {
-    let mut lines = app.labels.iter().map(chunk_label).collect::<Vec<Vec<ModalChunk>>>();
+    let (width, height) = app.get_modal_size();
+    let rows = app.get_modal_rows();
+    let mut lines = rows
+        .iter()
+        .skip(app.modal_offset)
+        .take(height - 1)
+        .map(|&x| chunk_label(&app.labels[x], width.saturating_sub(20)))
+        .collect::<Vec<Vec<ModalChunk>>>();
+
+    let title = if app.is_filter_mode() || !app.modal_filter.is_empty() {
+        format!(" /{} ", app.modal_filter.text)
+    } else {
+        String::new()
+    };
+    let count = if rows.len() >= height {
+        format!(" {}/{} ─", app.get_modal_line() + app.modal_offset, rows.len())
+    } else {
+        String::new()
+    };
 
     let blank = vec![ModalChunk {
-        text: format!(" │{:width$}│ ", "", width = 38),
+        text: format!(" │{:width$}│ ", "", width = width.saturating_sub(2)),
         color: Color::Reset,
         is_name: false,
     }];
     let top = vec![ModalChunk {
-        text: format!(" ┌{:─>width$}┐ ", "", width = 38),
+        text: format!(
+            " ┌{}{:─>width$}┐ ",
+            title,
+            "",
+            width = width.saturating_sub(2 + title.chars().count())
+        ),
         color: Color::Reset,
         is_name: false,
     }];
     let bottom = vec![ModalChunk {
-        text: format!(" └{:─>width$}┘ ", "", width = 38),
+        text: format!(
+            " └{:─>width$}{}┘ ",
+            "",
+            count,
+            width = width.saturating_sub(2 + count.chars().count())
+        ),
         color: Color::Reset,
         is_name: false,
     }];
 
     let color = app.labels[app.modal_row].color;
+    let shown = std::cmp::min(app::COLORS.len(), width.saturating_sub(3) / 2);
     let mut colors: Vec<ModalChunk> = app::COLORS
         .iter()
+        .take(shown)
         .map(|x| ModalChunk {
             text: if x == &color {
                 "◄►".to_owned()
@@ -372,20 +607,20 @@ fn chunk_modal_lines(app: &mut App) -> Vec<Vec<ModalChunk>> {
         },
     );
     colors.push(ModalChunk {
-        text: " │ ".to_owned(),
+        text: format!("{:width$}│ ", "", width = width.saturating_sub(3 + 2 * shown)),
         color: Color::Reset,
         is_name: false,
     });
 
     lines.insert(0, top);
-    lines.extend(std::iter::repeat(blank).take(25usize.saturating_sub(lines.len())));
+    lines.extend(std::iter::repeat_n(blank, height.saturating_sub(lines.len())));
     lines.push(colors);
     lines.push(bottom);
 
     lines
 }
 
-fn chunk_label(label: &Label) -> Vec<ModalChunk> {
+fn chunk_label(label: &Label, width: usize) -> Vec<ModalChunk> {
     let mut chunks = Vec::new();
 
     chunks.push(ModalChunk {
@@ -427,13 +662,13 @@ fn chunk_label(label: &Label) -> Vec<ModalChunk> {
     });
 
     chunks.push(ModalChunk {
-        text: "    ".to_owned(),
+        text: format!(" {}  ", label.hotkey.unwrap_or(' ')),
         color: Color::Reset,
         is_name: false,
     });
 
     chunks.push(ModalChunk {
-        text: format!("{:width$}", label.name, width = 20),
+        text: format!("{:width$.width$}", label.name),
         color: Color::Reset,
         is_name: true,
     });
@@ -446,3 +681,39 @@ fn chunk_label(label: &Label) -> Vec<ModalChunk> {
 
     chunks
 }
+
+#[test]
+fn test_render_offset_nowrap() {
+    let text = format!("{}\n{}", "é€".repeat(20), "é".repeat(50));
+    let mut app = crate::io::load_test_app(&text, &[(0, 5, "PER")]);
+    crate::settings::set_option(&mut app, "nowrap").unwrap();
+    crate::io::rewrap(&mut app);
+
+    app.cursor_column = 97;
+    common::manage_horizontal_scroll(&mut app);
+    assert!(app.offset_column == 17);
+
+    let mut buffer = Vec::new();
+    render_offset(&app, &mut buffer).unwrap();
+
+    let output = String::from_utf8(buffer).unwrap();
+    assert!(output.contains(&format!("m€{}\u{1b}", "é€".repeat(15))));
+    assert!(output.contains(&format!("m{}\u{1b}", "é".repeat(39))));
+}
+
+#[test]
+fn test_modal_lines_fit_window() {
+    let mut app = crate::io::load_test_app("alpha", &[(0, 5, "PER")]);
+    app.set_window(terminal::WindowSize {
+        rows: 20,
+        columns: 80,
+        width: 0,
+        height: 0,
+    });
+
+    let lines = chunk_modal_lines(&mut app);
+    assert!(lines.len() == 19 && app.modal_start_row + lines.len() <= 19);
+
+    app.settings.modal_height = 5;
+    assert!(chunk_modal_lines(&mut app).len() == 7);
+}
@@ -0,0 +1,189 @@
+use crate::{
+    app::{App, Mode, Visual},
+    common,
+};
+
+impl App {
+    pub fn is_review_mode(&self) -> bool {
+        self.mode == Mode::Review
+    }
+
+    pub fn set_review_mode(&mut self) {
+        self.mode = Mode::Review;
+    }
+
+    pub fn get_review_progress(&self) -> (usize, usize) {
+        let spans = self.get_span_heads();
+        let reviewed = spans
+            .iter()
+            .filter(|(row, position)| self.lines[*row].tags[*position].is_reviewed)
+            .count();
+
+        (reviewed, spans.len())
+    }
+
+    fn get_review_current(&self) -> Option<(usize, usize)> {
+        let row = self.cursor_row + self.offset_row;
+
+        self.get_current_line()
+            .tags
+            .iter()
+            .position(|x| !x.has_line_prev && x.start == self.cursor_column)
+            .map(|position| (row, position))
+    }
+
+    pub fn review_seek(&mut self, is_forward: bool, is_inclusive: bool, is_unreviewed: bool) {
+        let cursor = (self.cursor_row + self.offset_row, self.cursor_column);
+
+        let mut spans = self
+            .get_span_heads()
+            .into_iter()
+            .filter(|(row, position)| !is_unreviewed || !self.lines[*row].tags[*position].is_reviewed)
+            .map(|(row, position)| (row, self.lines[row].tags[position].start))
+            .collect::<Vec<(usize, usize)>>();
+
+        if !is_forward {
+            spans.reverse();
+        }
+
+        let next = spans
+            .iter()
+            .find(|&&x| match (is_forward, is_inclusive) {
+                (true, true) => x >= cursor,
+                (true, false) => x > cursor,
+                (false, true) => x <= cursor,
+                (false, false) => x < cursor,
+            })
+            .or(spans.first());
+
+        if let Some(&(row, column)) = next {
+            common::move_to_center(self, row, column);
+        } else {
+            self.set_normal_mode();
+        }
+
+        self.change |= 0b0001;
+    }
+
+    pub fn review_a(&mut self) {
+        if let Some((row, position)) = self.get_review_current() {
+            for (row, position) in self.get_span(row, position) {
+                self.lines[row].tags[position].is_reviewed = true;
+            }
+
+            self.is_dirty = true;
+        }
+
+        self.review_seek(true, false, true);
+    }
+
+    pub fn review_e(&mut self) {
+        if let Some((row, position)) = self.get_review_current() {
+            let span = self.get_span(row, position);
+            let label = self.lines[row].tags[position].label;
+
+            self.review_tags = span
+                .iter()
+                .map(|&(row, position)| (row, self.lines[row].tags[position].clone()))
+                .collect();
+            self.visual = self
+                .review_tags
+                .iter()
+                .map(|(row, tag)| Visual {
+                    row: *row,
+                    start: tag.start,
+                    end: tag.end - 1,
+                })
+                .collect();
+
+            self.labels[self.modal_active].is_active = false;
+            self.modal_active = label;
+            self.labels[self.modal_active].is_active = true;
+
+            let &(row, _) = span.last().expect("Error retrieving span end");
+            let column = self.visual.last().map(|x| x.end).unwrap_or(0);
+
+            common::move_to_center(self, row, column);
+            self.set_visual_mode();
+            self.change |= 0b0011;
+        }
+    }
+
+    /// Leaves Visual mode, going back to Review mode when the selection came from `review_e`.
+    /// The reviewed tags stay in place unless `tag_with` replaced them.
+    pub fn leave_visual_mode(&mut self) {
+        let Some(&(row, ref tag)) = self.review_tags.first() else {
+            self.set_normal_mode();
+            return;
+        };
+
+        let column = tag.start;
+        self.review_tags.clear();
+        self.visual.clear();
+
+        common::move_to_position(self, row, column);
+        self.set_review_mode();
+        self.review_seek(true, true, false);
+    }
+
+    pub fn review_j(&mut self) {
+        self.review_seek(true, false, false);
+    }
+
+    pub fn review_k(&mut self) {
+        self.review_seek(false, false, false);
+    }
+
+    pub fn review_r(&mut self) {
+        if let Some((row, position)) = self.get_review_current() {
+            for (row, position) in self.get_span(row, position) {
+                let tag = &mut self.lines[row].tags[position];
+                tag.label = self.modal_active;
+                tag.is_reviewed = true;
+            }
+
+            self.is_dirty = true;
+            self.change |= 0b0011;
+        }
+
+        self.review_seek(true, false, true);
+    }
+
+    pub fn review_x(&mut self) {
+        if let Some((row, position)) = self.get_review_current() {
+            for (row, position) in self.get_span(row, position) {
+                self.lines[row].tags.remove(position);
+            }
+
+            self.is_dirty = true;
+            self.change |= 0b0011;
+        }
+
+        self.review_seek(true, true, true);
+    }
+
+    pub fn review_esc(&mut self) {
+        self.set_normal_mode();
+        self.change |= 0b0001;
+    }
+}
+
+#[test]
+fn test_review_edit() {
+    let tags = |app: &App| {
+        app.lines[0]
+            .tags
+            .iter()
+            .map(|x| (x.start, x.end))
+            .collect::<Vec<(usize, usize)>>()
+    };
+
+    let mut app = crate::io::load_test_app("alpha beta gamma", &[(0, 10, "PER"), (11, 16, "ORG")]);
+    "rev".chars().for_each(|x| app.dispatch(x));
+    assert!(app.is_review_mode() && app.visual.is_empty() && !app.is_dirty);
+    assert!(tags(&app) == [(0, 10), (11, 16)] && app.cursor_column == 0);
+
+    "ebhht".chars().for_each(|x| app.dispatch(x));
+    assert!(app.is_review_mode() && app.is_dirty);
+    assert!(tags(&app) == [(11, 16), (0, 5)] && app.lines[0].tags[1].is_reviewed);
+}
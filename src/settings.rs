@@ -0,0 +1,207 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::app::App;
+
+pub const OPTIONS: &[&str] = &[
+    "center",
+    "labellimit=",
+    "margin=",
+    "modalheight=",
+    "modalwidth=",
+    "namelimit=",
+    "nocenter",
+    "nowrap",
+    "overlapcolor=",
+    "scrolloff=",
+    "textwidth=",
+    "visualcolor=",
+    "wrap",
+];
+
+#[derive(Debug)]
+pub struct Settings {
+    pub is_centered: bool,
+    pub is_overflow: bool,
+    pub label_limit: usize,
+    pub margin: usize,
+    pub modal_height: usize,
+    pub modal_width: usize,
+    pub name_limit: usize,
+    pub overlap_color: u8,
+    pub scrolloff: usize,
+    pub text_width: usize,
+    pub visual_color: u8,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            is_centered: false,
+            is_overflow: false,
+            label_limit: 100,
+            margin: 0,
+            modal_height: 25,
+            modal_width: 40,
+            name_limit: 20,
+            overlap_color: 160,
+            scrolloff: 0,
+            text_width: 0,
+            visual_color: 172,
+        }
+    }
+}
+
+pub fn load(app: &mut App, path: &Path) -> Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let table: toml::Table = toml::from_str(&std::fs::read_to_string(path)?)?;
+
+    for (name, value) in table {
+        let option = match value {
+            toml::Value::Boolean(true) => name,
+            toml::Value::Boolean(false) => format!("no{}", name),
+            toml::Value::Integer(n) => format!("{}={}", name, n),
+            toml::Value::String(s) => format!("{}={}", name, s),
+            _ => return Err(anyhow::Error::msg(format!("Invalid value for option: {}", name))),
+        };
+
+        set_option(app, &option)?;
+    }
+
+    app.set_modal_position();
+    Ok(())
+}
+
+/// Applies a single `name`, `noname` or `name=value` option, returning whether the text needs rewrapping.
+pub fn set_option(app: &mut App, option: &str) -> Result<bool> {
+    let settings = &mut app.settings;
+
+    match option.split_once('=') {
+        None if option == "wrap" => settings.is_overflow = false,
+        None if option == "nowrap" => settings.is_overflow = true,
+        None if option == "center" => settings.is_centered = true,
+        None if option == "nocenter" => settings.is_centered = false,
+        Some(("textwidth" | "tw", value)) => settings.text_width = parse_number(value)?,
+        Some(("margin", value)) => settings.margin = parse_number(value)?,
+        Some(("scrolloff" | "so", value)) => {
+            settings.scrolloff = parse_number(value)?;
+            return Ok(false);
+        }
+        Some(("labellimit", value)) => {
+            let value = parse_number(value)?;
+            check_bounds("labellimit", value, 1, usize::MAX)?;
+            settings.label_limit = value;
+            return Ok(false);
+        }
+        Some(("namelimit", value)) => {
+            let value = parse_number(value)?;
+            check_bounds("namelimit", value, 1, settings.modal_width - 20)?;
+            settings.name_limit = value;
+            return Ok(false);
+        }
+        Some(("modalheight", value)) => {
+            let value = parse_number(value)?;
+            check_bounds("modalheight", value, 2, usize::MAX)?;
+            settings.modal_height = value;
+            app.set_modal_position();
+            return Ok(false);
+        }
+        Some(("modalwidth", value)) => {
+            let value = parse_number(value)?;
+            check_bounds(
+                "modalwidth",
+                value,
+                std::cmp::max(settings.name_limit + 20, 40),
+                usize::MAX,
+            )?;
+            settings.modal_width = value;
+            app.set_modal_position();
+            return Ok(false);
+        }
+        Some(("overlapcolor", value)) => {
+            settings.overlap_color = parse_color(value)?;
+            return Ok(false);
+        }
+        Some(("visualcolor", value)) => {
+            settings.visual_color = parse_color(value)?;
+            return Ok(false);
+        }
+        _ => return Err(anyhow::Error::msg(format!("Unknown option: {}", option))),
+    }
+
+    Ok(true)
+}
+
+pub fn format_options(settings: &Settings) -> Vec<String> {
+    let flag = |name: &str, is_set: bool| if is_set { name.to_owned() } else { format!("no{}", name) };
+
+    vec![
+        flag("center", settings.is_centered),
+        format!("labellimit={}", settings.label_limit),
+        format!("margin={}", settings.margin),
+        format!("modalheight={}", settings.modal_height),
+        format!("modalwidth={}", settings.modal_width),
+        format!("namelimit={}", settings.name_limit),
+        format!("overlapcolor={}", settings.overlap_color),
+        format!("scrolloff={}", settings.scrolloff),
+        format!("textwidth={}", settings.text_width),
+        format!("visualcolor={}", settings.visual_color),
+        flag("wrap", !settings.is_overflow),
+    ]
+}
+
+pub fn parse_number(s: &str) -> Result<usize> {
+    s.parse::<usize>()
+        .map_err(|_| anyhow::Error::msg(format!("Invalid number: {}", s)))
+}
+
+fn parse_color(s: &str) -> Result<u8> {
+    s.parse::<u8>()
+        .map_err(|_| anyhow::Error::msg(format!("Invalid color: {}", s)))
+}
+
+fn check_bounds(name: &str, value: usize, min: usize, max: usize) -> Result<()> {
+    if value < min || value > max {
+        return Err(anyhow::Error::msg(format!("Out of range for {}: {}", name, value)));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_set_option() {
+    let mut app = crate::io::load_test_app("alpha", &[(0, 5, "PER")]);
+
+    assert!(set_option(&mut app, "nowrap").unwrap() && app.settings.is_overflow);
+    assert!(set_option(&mut app, "wrap").unwrap() && !app.settings.is_overflow);
+    assert!(set_option(&mut app, "center").unwrap() && app.settings.is_centered);
+    assert!(set_option(&mut app, "tw=60").unwrap() && app.settings.text_width == 60);
+    assert!(set_option(&mut app, "margin=4").unwrap() && app.settings.margin == 4);
+    assert!(!set_option(&mut app, "so=3").unwrap() && app.settings.scrolloff == 3);
+    assert!(!set_option(&mut app, "visualcolor=33").unwrap() && app.settings.visual_color == 33);
+
+    assert!(set_option(&mut app, "textwidth=-1").is_err());
+    assert!(set_option(&mut app, "overlapcolor=256").is_err());
+    assert!(set_option(&mut app, "wrap=1").is_err());
+    assert!(set_option(&mut app, "colors").is_err());
+}
+
+#[test]
+fn test_set_option_bounds() {
+    let mut app = crate::io::load_test_app("alpha", &[(0, 5, "PER")]);
+
+    assert!(set_option(&mut app, "labellimit=0").is_err());
+    assert!(set_option(&mut app, "modalheight=1").is_err());
+    assert!(set_option(&mut app, "modalwidth=39").is_err());
+    assert!(set_option(&mut app, "namelimit=0").is_err());
+    assert!(set_option(&mut app, "namelimit=21").is_err());
+
+    set_option(&mut app, "modalwidth=50").unwrap();
+    set_option(&mut app, "namelimit=30").unwrap();
+    assert!(set_option(&mut app, "modalwidth=49").is_err());
+    assert!(app.settings.modal_width == 50 && app.settings.name_limit == 30);
+}
@@ -1,6 +1,6 @@
 use crate::{
     app::{App, Mode, Visual},
-    common,
+    common::{self, TextObject},
 };
 
 impl App {
@@ -10,117 +10,315 @@ impl App {
 
     pub fn set_visual_mode(&mut self) {
         self.mode = Mode::Visual;
+        self.is_linewise = false;
     }
 
     fn set_visual_end(&mut self) {
-        self.visual
+        if let Some(region) = self
+            .visual
             .iter_mut()
             .find(|x| x.row == self.cursor_row + self.offset_row)
-            .map(|x| x.end = self.cursor_column);
+        {
+            region.end = self.cursor_column;
+        }
+    }
+
+    pub fn get_visual_anchor(&self) -> (usize, usize) {
+        let region = match (self.visual.first(), self.visual.last()) {
+            (Some(first), Some(last)) if first.row == self.cursor_row + self.offset_row => last,
+            (Some(first), _) => first,
+            _ => {
+                return (self.cursor_row + self.offset_row, self.cursor_column);
+            }
+        };
+
+        (region.row, region.start)
+    }
+
+    pub fn set_visual_range(&mut self, anchor: (usize, usize)) {
+        let row = self.cursor_row + self.offset_row;
+        let line_end = |x: usize| self.lines[x].width.saturating_sub(1);
+
+        let (anchor, position) = match anchor.0 {
+            _ if !self.is_linewise => (anchor, (row, self.cursor_column)),
+            x if x <= row => ((x, 0), (row, line_end(row))),
+            x => ((x, line_end(x)), (row, 0)),
+        };
+
+        self.visual = self.get_visual_regions(anchor, position);
+        self.change |= 0b0011;
+    }
+
+    pub fn get_visual_regions(&self, anchor: (usize, usize), position: (usize, usize)) -> Vec<Visual> {
+        let (anchor_row, anchor_column) = anchor;
+        let (row, column) = position;
+
+        if row >= anchor_row {
+            (anchor_row..=row)
+                .map(|x| Visual {
+                    row: x,
+                    start: if x == anchor_row { anchor_column } else { 0 },
+                    end: if x == row {
+                        column
+                    } else {
+                        self.lines[x].width.saturating_sub(1)
+                    },
+                })
+                .collect()
+        } else {
+            (row..=anchor_row)
+                .map(|x| Visual {
+                    row: x,
+                    start: if x == anchor_row {
+                        anchor_column
+                    } else {
+                        self.lines[x].width.saturating_sub(1)
+                    },
+                    end: if x == row { column } else { 0 },
+                })
+                .collect()
+        }
+    }
+
+    pub fn get_visual_range(&self) -> Option<(usize, usize)> {
+        let (first, last) = (self.visual.first()?, self.visual.last()?);
+
+        Some((
+            self.get_absolute_position(first.row, self.get_visual_bounds(first.row).0),
+            self.get_absolute_position(last.row, self.get_visual_bounds(last.row).1),
+        ))
+    }
+
+    pub fn select_text_object(&mut self, object: TextObject, is_inner: bool) {
+        let cursor = self.get_absolute_position(self.cursor_row + self.offset_row, self.cursor_column);
+        let (start, end) = self.get_visual_range().unwrap_or((cursor, cursor + 1));
+
+        let is_extending = end - start > 1 && cursor + 1 == end;
+        let position = if is_extending { end } else { cursor };
+
+        if let Some((object_start, object_end)) = common::find_text_object(self, position, object, is_inner) {
+            let anchor = self.find_absolute_position(if is_extending { start } else { object_start });
+            let (row, column) = self.find_absolute_position(object_end - 1);
+
+            common::move_to_position(self, row, column);
+            self.set_visual_range(anchor);
+        }
     }
 
-    pub fn visual_m(&mut self) {
-        self.set_modal_mode();
+    pub fn visual_pick_label(&mut self) {
+        self.open_picker();
+    }
+
+    pub fn visual_backslash(&mut self) {
+        match self.labels.iter().position(|x| x.hotkey == Some(self.argument)) {
+            Some(label) => {
+                self.tag_with(label);
+                self.leave_visual_mode();
+            }
+            None => {
+                self.message = format!("No label for hotkey: {}", self.argument);
+                self.change |= 0b0001;
+            }
+        }
+    }
+
+    #[allow(non_snake_case)]
+    pub fn visual_T(&mut self) {
+        self.open_modal();
         self.change = 0b_0001_0000;
     }
 
     pub fn visual_v(&mut self) {
-        self.set_normal_mode();
-        self.set_visual_end();
-        self.change = 0b0001;
+        if self.is_linewise {
+            let anchor = self.get_visual_anchor();
+            self.is_linewise = false;
+            self.set_visual_range(anchor);
+        } else {
+            self.set_visual_end();
+            self.leave_visual_mode();
+            self.change = 0b0001;
+        }
+    }
+
+    #[allow(non_snake_case)]
+    pub fn visual_V(&mut self) {
+        if self.is_linewise {
+            self.leave_visual_mode();
+            self.change = 0b0001;
+        } else {
+            let anchor = self.get_visual_anchor();
+            self.is_linewise = true;
+            self.set_visual_range(anchor);
+        }
     }
 
     pub fn visual_h(&mut self) {
+        let anchor = self.get_visual_anchor();
         common::handle_h(self);
-        self.set_visual_end();
-        self.change = 0b0011;
+        self.set_visual_range(anchor);
     }
 
     pub fn visual_j(&mut self) {
+        let anchor = self.get_visual_anchor();
         common::handle_j(self);
-
-        if self.change != 0 {
-            self.visual
-                .iter_mut()
-                .find(|x| x.row == self.cursor_row + self.offset_row - 1)
-                .map(|x| x.end = self.lines[self.cursor_row + self.offset_row - 1].width - 1);
-
-            if let Some(region) = self
-                .visual
-                .iter_mut()
-                .find(|x| x.row == self.cursor_row + self.offset_row)
-            {
-                region.end = self.cursor_column;
-                self.visual.remove(0);
-            } else {
-                let region = Visual {
-                    start: 0,
-                    end: self.cursor_column,
-                    row: self.cursor_row + self.offset_row,
-                };
-                self.visual.push(region);
-            }
-
-            self.change = 0b0011;
-        }
+        self.set_visual_range(anchor);
     }
 
     pub fn visual_k(&mut self) {
+        let anchor = self.get_visual_anchor();
         common::handle_k(self);
-
-        if self.change != 0 {
-            self.visual
-                .iter_mut()
-                .find(|x| x.row == self.cursor_row + self.offset_row + 1)
-                .map(|x| x.end = 0);
-
-            if let Some(region) = self
-                .visual
-                .iter_mut()
-                .find(|x| x.row == self.cursor_row + self.offset_row)
-            {
-                region.end = self.cursor_column;
-                self.visual.pop();
-            } else {
-                let region = Visual {
-                    start: self.get_current_line_width() - 1,
-                    end: self.cursor_column,
-                    row: self.cursor_row + self.offset_row,
-                };
-                self.visual.insert(0, region);
-            }
-
-            self.change = 0b0011;
-        }
+        self.set_visual_range(anchor);
     }
 
     pub fn visual_l(&mut self) {
+        let anchor = self.get_visual_anchor();
         common::handle_l(self);
-        self.set_visual_end();
-        self.change = 0b0011;
+        self.set_visual_range(anchor);
     }
 
     pub fn visual_s(&mut self) {
+        let anchor = self.get_visual_anchor();
         common::handle_s(self);
-        self.set_visual_end();
-        self.change = 0b0011;
+        self.set_visual_range(anchor);
     }
 
-    pub fn visual_e(&mut self) {
-        common::handle_e(self);
-        self.set_visual_end();
-        self.change = 0b0011;
+    pub fn visual_dollar(&mut self) {
+        let anchor = self.get_visual_anchor();
+        common::handle_dollar(self);
+        self.set_visual_range(anchor);
+    }
+
+    pub fn visual_scroll_down(&mut self) {
+        let anchor = self.get_visual_anchor();
+        common::handle_scroll_down(self, 1);
+        self.set_visual_range(anchor);
+    }
+
+    pub fn visual_scroll_up(&mut self) {
+        let anchor = self.get_visual_anchor();
+        common::handle_scroll_up(self, 1);
+        self.set_visual_range(anchor);
+    }
+
+    pub fn visual_o(&mut self) {
+        let (row, column) = self.get_visual_anchor();
+        let position = (self.cursor_row + self.offset_row, self.cursor_column);
+
+        common::move_to_position(self, row, column);
+        self.set_visual_range(position);
     }
 
     pub fn visual_w(&mut self) {
-        common::handle_w(self);
-        self.set_visual_end();
-        self.change = 0b0011;
+        let anchor = self.get_visual_anchor();
+        common::handle_w(self, false);
+        self.set_visual_range(anchor);
+    }
+
+    #[allow(non_snake_case)]
+    pub fn visual_W(&mut self) {
+        let anchor = self.get_visual_anchor();
+        common::handle_w(self, true);
+        self.set_visual_range(anchor);
+    }
+
+    pub fn visual_e(&mut self) {
+        let anchor = self.get_visual_anchor();
+        common::handle_e(self, false);
+        self.set_visual_range(anchor);
+    }
+
+    #[allow(non_snake_case)]
+    pub fn visual_end_WORD(&mut self) {
+        let anchor = self.get_visual_anchor();
+        common::handle_e(self, true);
+        self.set_visual_range(anchor);
     }
 
     pub fn visual_b(&mut self) {
-        common::handle_b(self);
-        self.set_visual_end();
-        self.change = 0b0011;
+        let anchor = self.get_visual_anchor();
+        common::handle_b(self, false);
+        self.set_visual_range(anchor);
+    }
+
+    #[allow(non_snake_case)]
+    pub fn visual_B(&mut self) {
+        let anchor = self.get_visual_anchor();
+        common::handle_b(self, true);
+        self.set_visual_range(anchor);
+    }
+
+    pub fn visual_ge(&mut self) {
+        let anchor = self.get_visual_anchor();
+        common::handle_ge(self, false);
+        self.set_visual_range(anchor);
+    }
+
+    #[allow(non_snake_case)]
+    pub fn visual_gE(&mut self) {
+        let anchor = self.get_visual_anchor();
+        common::handle_ge(self, true);
+        self.set_visual_range(anchor);
+    }
+
+    pub fn visual_f(&mut self) {
+        let anchor = self.get_visual_anchor();
+        common::handle_find(self, 'f');
+        self.set_visual_range(anchor);
+    }
+
+    #[allow(non_snake_case)]
+    pub fn visual_F(&mut self) {
+        let anchor = self.get_visual_anchor();
+        common::handle_find(self, 'F');
+        self.set_visual_range(anchor);
+    }
+
+    pub fn visual_gt(&mut self) {
+        let anchor = self.get_visual_anchor();
+        common::handle_find(self, 't');
+        self.set_visual_range(anchor);
+    }
+
+    #[allow(non_snake_case)]
+    pub fn visual_gT(&mut self) {
+        let anchor = self.get_visual_anchor();
+        common::handle_find(self, 'T');
+        self.set_visual_range(anchor);
+    }
+
+    pub fn visual_semicolon(&mut self) {
+        let anchor = self.get_visual_anchor();
+        common::handle_find_repeat(self, false);
+        self.set_visual_range(anchor);
+    }
+
+    pub fn visual_comma(&mut self) {
+        let anchor = self.get_visual_anchor();
+        common::handle_find_repeat(self, true);
+        self.set_visual_range(anchor);
+    }
+
+    pub fn visual_iw(&mut self) {
+        self.select_text_object(TextObject::Word, true);
+    }
+
+    pub fn visual_aw(&mut self) {
+        self.select_text_object(TextObject::Word, false);
+    }
+
+    pub fn visual_is(&mut self) {
+        self.select_text_object(TextObject::Sentence, true);
+    }
+
+    pub fn visual_as(&mut self) {
+        self.select_text_object(TextObject::Sentence, false);
+    }
+
+    pub fn visual_ip(&mut self) {
+        self.select_text_object(TextObject::Paragraph, true);
+    }
+
+    pub fn visual_iquote(&mut self) {
+        self.select_text_object(TextObject::Quote, true);
     }
 }